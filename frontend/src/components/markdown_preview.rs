@@ -1,39 +1,279 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 
-use config::EmittedMarkdown;
+use config::{EmittedMarkdown, SlideSettings, Stack};
 use leptos::{
+    ev,
     html::{article, div},
     prelude::*,
+    spawn::spawn_local,
 };
 use tachys::dom::document;
-use wasm_bindgen::JsCast;
-use web_sys::HtmlImageElement;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use web_sys::{Element, HtmlImageElement};
+
+use crate::utils::{goto_anchor, open_markdown};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = mermaid, js_name = initialize)]
+    fn mermaid_initialize(config: &JsValue);
+
+    #[wasm_bindgen(js_namespace = mermaid, js_name = render, catch)]
+    async fn mermaid_render(id: &str, text: &str) -> Result<JsValue, JsValue>;
+}
+
+thread_local! {
+    /// bumped by every render of a selector, an older one of the same selector
+    /// still waiting on mermaid gives up
+    static MERMAID_GENERATIONS: RefCell<HashMap<&'static str, usize>> =
+        RefCell::new(HashMap::new());
+    /// mermaid draws in a temporary element of the given id, so no two renders share one
+    static MERMAID_ID: Cell<usize> = const { Cell::new(0) };
+}
 
-pub fn markdown_preview(port: Rc<RefCell<u16>>) -> impl IntoView {
+const MERMAID_SELECTOR: &str = "#preview pre.mermaid";
+const MERMAID_SOURCE: &str = "data-mermaid-source";
+const FRAGMENT_SELECTOR: &str = "#preview .fragment-marker";
+const FRAGMENT_MARKER_CLASS: &str = "fragment-marker";
+const FRAGMENT_ITEM_CLASS: &str = "fragment-item";
+const INTERNAL_LINK_SELECTOR: &str = "a[href^='#']";
+const LINK_SELECTOR: &str = "a[href]";
+const MARKDOWN_EXTENSION: &str = ".md";
+const PREVIEW_CLASS: &str = "absolute top-1/2 left-1/2 transform -translate-x-1/2 -translate-y-1/2 p-5 prose-img:rounded-xl prose-pre:overflow-auto prose-code:bg-base-300 prose-code:px-[5.5px] prose-code:font-normal prose-code:rounded-[0.3125rem] prose-code:overflow-auto prose-a:no-underline prose-a:text-info print:block";
+
+pub fn markdown_preview(
+    port: Rc<RefCell<u16>>,
+    dark_theme: impl Fn() -> bool + 'static,
+) -> impl IntoView {
     let markdown = use_context::<Markdown>().unwrap();
 
     let md = move || markdown.content.get();
+    let class = move || {
+        markdown.settings.with(|x| {
+            let layout = x.layout.as_ref().map(|x| format!("layout-{x}"));
+            let classes = x.classes.iter().cloned().chain(layout);
+            format!("{PREVIEW_CLASS} {}", classes.collect::<Vec<_>>().join(" "))
+        })
+    };
+
+    Effect::new(move |_| {
+        let _ = md();
+        fix_image_sources(*port.borrow());
+    });
+
+    Effect::new(move |_| {
+        let _ = md();
+        let dark = dark_theme();
+        spawn_local(render_mermaid(dark, MERMAID_SELECTOR));
+    });
+
+    Effect::new(move |_| {
+        let _ = md();
+        reveal_fragments(markdown.fragment.get());
+    });
 
     Effect::new(move |_| {
         let _ = md();
-        let images = document().get_elements_by_tag_name("img");
-        for i in 0..images.length() {
-            let image: HtmlImageElement = images.item(i).unwrap().dyn_into().unwrap();
-            image.get_attribute("src").inspect(|x| {
-                if !x.starts_with("http") {
-                    let content = format!("http://localhost:{}/{}", port.borrow(), x);
-                    image.set_src(&content);
+        if let Some(anchor) = markdown.anchor.get_untracked() {
+            if let Some(heading) = preview_element(&anchor) {
+                markdown.anchor.set(None);
+                heading.scroll_into_view();
+            }
+        }
+    });
+    let follow_link = move |event: ev::MouseEvent| {
+        if let Some((path, anchor)) = markdown_link(&event) {
+            event.prevent_default();
+            markdown.anchor.set(anchor.clone());
+            spawn_local(async move {
+                if !open_markdown(path, anchor).await {
+                    markdown.anchor.set(None);
                 }
             });
+        } else if let Some(anchor) = internal_link(&event) {
+            event.prevent_default();
+            follow_anchor(markdown, anchor);
         }
-    });
+    };
 
-    div()
-        .attr("class", "overflow-auto")
-        .child(article()
+    div().attr("class", "overflow-auto").child(
+        article()
             .id("preview")
-            .class("absolute top-1/2 left-1/2 transform -translate-x-1/2 -translate-y-1/2 p-5 prose-img:rounded-xl prose-pre:bg-base-300 prose-pre:text-base-content prose-pre:overflow-auto prose-code:bg-base-300 prose-code:px-[5.5px] prose-code:font-normal prose-code:rounded-[0.3125rem] prose-code:overflow-auto prose-a:no-underline prose-a:text-info print:block")
-            .inner_html(md))
+            .class(class)
+            .on(ev::click, follow_link)
+            .inner_html(md),
+    )
+}
+
+/// scrolls to the heading of the `anchor` id, going to its slide first
+/// when it is not the current one.
+pub fn follow_anchor(markdown: Markdown, anchor: String) {
+    if let Some(heading) = preview_element(&anchor) {
+        heading.scroll_into_view();
+        return;
+    }
+    markdown.anchor.set(Some(anchor.clone()));
+    spawn_local(async move {
+        if !goto_anchor(anchor).await {
+            markdown.anchor.set(None);
+        }
+    });
+}
+
+/// the anchor of the `#anchor` link clicked in the preview
+fn internal_link(event: &ev::MouseEvent) -> Option<String> {
+    let target: Element = event.target()?.dyn_into().ok()?;
+    let link = target.closest(INTERNAL_LINK_SELECTOR).ok()??;
+    let href = link.get_attribute("href")?;
+    let anchor = js_sys::decode_uri_component(href.trim_start_matches('#'))
+        .ok()?
+        .as_string()?;
+    (!anchor.is_empty()).then_some(anchor)
+}
+
+/// the path and the anchor of the link to a local markdown file clicked in the preview
+fn markdown_link(event: &ev::MouseEvent) -> Option<(String, Option<String>)> {
+    let target: Element = event.target()?.dyn_into().ok()?;
+    let link = target.closest(LINK_SELECTOR).ok()??;
+    let href = link.get_attribute("href")?;
+    if href.contains(':') || href.starts_with('/') {
+        return None;
+    }
+    let (path, anchor) = match href.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (href.as_str(), None),
+    };
+    if !path.to_lowercase().ends_with(MARKDOWN_EXTENSION) {
+        return None;
+    }
+    let decode = |x: &str| js_sys::decode_uri_component(x).ok()?.as_string();
+    let anchor = anchor.filter(|x| !x.is_empty()).and_then(decode);
+    Some((decode(path)?, anchor))
+}
+
+/// the element of the preview with the `id`
+fn preview_element(id: &str) -> Option<Element> {
+    document()
+        .get_element_by_id(id)
+        .filter(|x| x.closest("#preview").ok().flatten().is_some())
+}
+
+/// points the relative image sources of the page to the assets server
+pub fn fix_image_sources(port: u16) {
+    let images = document().get_elements_by_tag_name("img");
+    for i in 0..images.length() {
+        let image: HtmlImageElement = images.item(i).unwrap().dyn_into().unwrap();
+        image.get_attribute("src").inspect(|x| {
+            if !x.starts_with("http") {
+                let content = format!("http://localhost:{}/{}", port, x);
+                image.set_src(&content);
+            }
+        });
+    }
+}
+
+/// marks the element of every fragment marker of the preview,
+/// or the block before it for a marker standing on its own,
+/// or the list item holding it for a list item marker,
+/// and shows the first `fragment` of them.
+fn reveal_fragments(fragment: usize) {
+    let Ok(markers) = document().query_selector_all(FRAGMENT_SELECTOR) else {
+        return;
+    };
+    for i in 0..markers.length() {
+        let Some(marker) = markers.item(i).and_then(|x| x.dyn_into::<Element>().ok()) else {
+            continue;
+        };
+        let target = if marker.class_list().contains(FRAGMENT_ITEM_CLASS) {
+            marker.closest("li").ok().flatten()
+        } else {
+            marker
+                .parent_element()
+                .filter(|x| x.id() != "preview")
+                .or_else(|| {
+                    // the block before adjacent markers is the one before all of them
+                    std::iter::successors(
+                        marker.previous_element_sibling(),
+                        Element::previous_element_sibling,
+                    )
+                    .find(|x| !x.class_list().contains(FRAGMENT_MARKER_CLASS))
+                })
+        };
+        let Some(target) = target else {
+            continue;
+        };
+        let classes = target.class_list();
+        classes.add_1("fragment").unwrap();
+        classes
+            .toggle_with_force("fragment-visible", (i as usize) < fragment)
+            .unwrap();
+    }
+}
+
+/// lays out every mermaid block matching `selector` with the bundled mermaid,
+/// the source is kept aside so a theme change can draw it again.
+///
+/// a render of the same selector started later, for a new slide or theme,
+/// supersedes this one.
+pub async fn render_mermaid(dark: bool, selector: &'static str) {
+    let generation = MERMAID_GENERATIONS.with(|x| {
+        let mut generations = x.borrow_mut();
+        let generation = generations.entry(selector).or_default();
+        *generation += 1;
+        *generation
+    });
+    let Ok(blocks) = document().query_selector_all(selector) else {
+        return;
+    };
+    let config = js_sys::Object::new();
+    let theme = if dark { "dark" } else { "default" };
+    let _ = js_sys::Reflect::set(&config, &"startOnLoad".into(), &false.into());
+    let _ = js_sys::Reflect::set(&config, &"theme".into(), &theme.into());
+    mermaid_initialize(&config);
+
+    for i in 0..blocks.length() {
+        let Some(block) = blocks.item(i).and_then(|x| x.dyn_into::<Element>().ok()) else {
+            continue;
+        };
+        let source = match block.get_attribute(MERMAID_SOURCE) {
+            Some(source) => source,
+            None => {
+                let source = block.text_content().unwrap_or_default();
+                let _ = block.set_attribute(MERMAID_SOURCE, &source);
+                source
+            }
+        };
+        let id = MERMAID_ID.with(|x| {
+            x.set(x.get() + 1);
+            x.get()
+        });
+        let output = mermaid_render(&format!("mermaid-{id}"), &source).await;
+        if MERMAID_GENERATIONS.with(|x| x.borrow()[selector]) != generation {
+            return;
+        }
+        match output {
+            Ok(output) => {
+                let svg = js_sys::Reflect::get(&output, &"svg".into())
+                    .ok()
+                    .and_then(|x| x.as_string())
+                    .unwrap_or_default();
+                block.class_list().remove_1("diagram-error").unwrap();
+                block.set_inner_html(&svg);
+            }
+            Err(err) => {
+                let message = err
+                    .dyn_into::<js_sys::Error>()
+                    .map(|x| String::from(x.message()))
+                    .unwrap_or_else(|_| "invalid mermaid diagram".to_string());
+                block.class_list().add_1("diagram-error").unwrap();
+                block.set_text_content(Some(&message));
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -41,6 +281,11 @@ pub struct Markdown {
     pub content: RwSignal<String>,
     pub current: RwSignal<usize>,
     pub len: RwSignal<usize>,
+    pub fragment: RwSignal<usize>,
+    pub stack: RwSignal<Stack>,
+    pub settings: RwSignal<SlideSettings>,
+    /// the heading to scroll to once the slide holding it is shown
+    pub anchor: RwSignal<Option<String>>,
 }
 
 impl From<EmittedMarkdown<String>> for Markdown {
@@ -48,6 +293,9 @@ impl From<EmittedMarkdown<String>> for Markdown {
         EmittedMarkdown {
             current,
             len,
+            fragment,
+            stack,
+            settings,
             content,
         }: EmittedMarkdown<String>,
     ) -> Self {
@@ -55,6 +303,10 @@ impl From<EmittedMarkdown<String>> for Markdown {
             content: RwSignal::new(content),
             current: RwSignal::new(current),
             len: RwSignal::new(len),
+            fragment: RwSignal::new(fragment),
+            stack: RwSignal::new(stack),
+            settings: RwSignal::new(settings),
+            anchor: RwSignal::new(None),
         }
     }
 }
@@ -65,6 +317,9 @@ impl Markdown {
         EmittedMarkdown {
             current,
             len,
+            fragment,
+            stack,
+            settings,
             content,
         }: EmittedMarkdown<String>,
     ) {
@@ -75,6 +330,15 @@ impl Markdown {
         if self.len.get_untracked() != len {
             self.len.set(len);
         }
+        if self.fragment.get_untracked() != fragment {
+            self.fragment.set(fragment);
+        }
+        if self.stack.get_untracked() != stack {
+            self.stack.set(stack);
+        }
+        if self.settings.get_untracked() != settings {
+            self.settings.set(settings);
+        }
     }
 }
 
@@ -84,6 +348,10 @@ impl Default for Markdown {
             content: RwSignal::new(String::default()),
             current: RwSignal::new(0),
             len: RwSignal::new(0),
+            fragment: RwSignal::new(0),
+            stack: RwSignal::new(Stack::default()),
+            settings: RwSignal::new(SlideSettings::default()),
+            anchor: RwSignal::new(None),
         }
     }
 }
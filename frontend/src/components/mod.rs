@@ -1,3 +1,6 @@
 // pub mod btn;
 pub mod help;
 pub mod markdown_preview;
+pub mod overview;
+pub mod presenter;
+pub mod toc;
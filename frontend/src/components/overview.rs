@@ -0,0 +1,139 @@
+use std::{cell::RefCell, rc::Rc};
+
+use leptos::{prelude::*, spawn::spawn_local};
+use tachys::dom::document;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+use super::markdown_preview::{fix_image_sources, render_mermaid};
+use crate::utils::{goto_slide, slides_overview};
+
+const OVERVIEW_COLUMNS: usize = 4;
+const THUMBNAIL_ID: &str = "overview-slide";
+const THUMBNAIL_IDS_SELECTOR: &str = ".overview-thumbnail [id]";
+const THUMBNAIL_MERMAID_SELECTOR: &str = ".overview-thumbnail pre.mermaid";
+
+/// every slide as a thumbnail, the selected one is highlighted
+/// and a click or Enter jumps to it.
+pub fn overview(port: Rc<RefCell<u16>>, dark_theme: impl Fn() -> bool + 'static) -> impl IntoView {
+    let overview = use_context::<Overview>().unwrap();
+
+    Effect::new(move |_| {
+        if overview.open.get() {
+            let _ = overview.slides.with(Vec::len);
+            fix_image_sources(*port.borrow());
+            remove_thumbnail_ids();
+            spawn_local(render_mermaid(dark_theme(), THUMBNAIL_MERMAID_SELECTOR));
+        }
+    });
+
+    Effect::new(move |_| {
+        let selected = overview.selected.get();
+        if let Some(thumbnail) = document().get_element_by_id(&format!("{THUMBNAIL_ID}-{selected}"))
+        {
+            thumbnail.scroll_into_view();
+        }
+    });
+
+    let thumbnails = move || {
+        overview
+            .slides
+            .get()
+            .into_iter()
+            .enumerate()
+            .map(|(i, content)| {
+                let border = move || {
+                    if overview.selected.get() == i {
+                        "border-success"
+                    } else {
+                        "border-base-300"
+                    }
+                };
+                view! {
+                    <button
+                        id=format!("{THUMBNAIL_ID}-{i}")
+                        class=move || format!("relative aspect-video overflow-hidden text-left border-4 rounded-box bg-base-100 {}", border())
+                        on:click=move |_| overview.jump(i)
+                    >
+                        <div class="overview-thumbnail prose prose-sm w-[200%] scale-50 origin-top-left p-4 pointer-events-none" inner_html=content />
+                        <span class="absolute bottom-1 right-2 badge badge-neutral">{i + 1}</span>
+                    </button>
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <Show when=move || overview.open.get()>
+            <div
+                class="fixed inset-0 z-40 grid content-start gap-4 p-6 overflow-auto bg-base-200 print:hidden"
+                style=format!("grid-template-columns: repeat({OVERVIEW_COLUMNS}, minmax(0, 1fr))")
+            >
+                {thumbnails}
+            </div>
+        </Show>
+    }
+}
+
+/// the thumbnails repeat the ids of the slides, a link to an anchor
+/// must still find the heading of the slide shown
+fn remove_thumbnail_ids() {
+    let Ok(elements) = document().query_selector_all(THUMBNAIL_IDS_SELECTOR) else {
+        return;
+    };
+    for i in 0..elements.length() {
+        if let Some(element) = elements.item(i).and_then(|x| x.dyn_into::<Element>().ok()) {
+            let _ = element.remove_attribute("id");
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Overview {
+    pub open: RwSignal<bool>,
+    pub slides: RwSignal<Vec<String>>,
+    pub selected: RwSignal<usize>,
+}
+
+impl Overview {
+    /// opens the grid on the `current` slide, zero based, or closes it
+    pub fn toggle(&self, current: usize) {
+        if self.open.get_untracked() {
+            self.open.set(false);
+            return;
+        }
+        let overview = *self;
+        spawn_local(async move {
+            overview.slides.set(slides_overview().await);
+            overview.selected.set(current);
+            overview.open.set(true);
+        });
+    }
+
+    /// moves the selection by `step` thumbnails, `rows` moves by whole rows
+    pub fn select(&self, step: isize, rows: bool) {
+        let step = if rows {
+            step * OVERVIEW_COLUMNS as isize
+        } else {
+            step
+        };
+        let last = self.slides.with_untracked(Vec::len).saturating_sub(1);
+        let selected = self.selected.get_untracked().saturating_add_signed(step);
+        self.selected.set(selected.min(last));
+    }
+
+    pub fn jump(&self, index: usize) {
+        self.open.set(false);
+        goto_slide(index);
+    }
+}
+
+impl Default for Overview {
+    fn default() -> Self {
+        Overview {
+            open: RwSignal::new(false),
+            slides: RwSignal::new(Vec::new()),
+            selected: RwSignal::new(0),
+        }
+    }
+}
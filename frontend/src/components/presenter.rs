@@ -0,0 +1,99 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use config::EmittedPresenter;
+use gloo::timers::callback::Interval;
+use leptos::prelude::*;
+
+use super::markdown_preview::{fix_image_sources, markdown_preview, Markdown};
+
+/// the speaker view: the current slide, the next one, the notes,
+/// the slide counter and the talk clock.
+pub fn presenter(
+    port: Rc<RefCell<u16>>,
+    dark_theme: impl Fn() -> bool + 'static,
+    duration: impl Fn() -> Option<u64> + Send + Sync + 'static,
+) -> impl IntoView {
+    let markdown = use_context::<Markdown>().unwrap();
+    let presenter = use_context::<Presenter>().unwrap();
+
+    let next = move || presenter.next.get();
+    let notes = move || presenter.notes.get();
+    let counter = move || format!("{} / {}", markdown.current.get(), markdown.len.get());
+
+    Effect::new({
+        let port = port.clone();
+        move |_| {
+            let _ = next();
+            fix_image_sources(*port.borrow());
+        }
+    });
+
+    let start = Rc::new(Cell::new(js_sys::Date::now()));
+    let elapsed = RwSignal::new(0);
+    Interval::new(1000, {
+        let start = start.clone();
+        move || elapsed.set(((js_sys::Date::now() - start.get()) / 1000.0) as u64)
+    })
+    .forget();
+    let reset = move |_| {
+        start.set(js_sys::Date::now());
+        elapsed.set(0);
+    };
+    let remaining = move || {
+        let total = duration()? * 60;
+        let elapsed = elapsed.get();
+        Some(if elapsed <= total {
+            clock(total - elapsed)
+        } else {
+            format!("-{}", clock(elapsed - total))
+        })
+    };
+
+    view! {
+        <div class="grid grid-cols-3 grid-rows-[auto_1fr_1fr] gap-4 h-screen p-4">
+            <header class="col-span-3 flex justify-between items-center text-2xl font-mono">
+                <span>{counter}</span>
+                <button class="btn btn-ghost text-2xl font-mono" title="reset the clock" on:click=reset>
+                    {move || clock(elapsed.get())}
+                </button>
+                <span class="text-warning">{remaining}</span>
+            </header>
+            <section class="relative col-span-2 row-span-2 border-2 border-base-300 rounded-box overflow-hidden">
+                {markdown_preview(port, dark_theme)}
+            </section>
+            <section class="border-2 border-base-300 rounded-box overflow-hidden p-2">
+                <div class="prose prose-sm scale-75 origin-top-left" inner_html=next />
+            </section>
+            <section class="prose prose-lg overflow-auto" inner_html=notes />
+        </div>
+    }
+}
+
+fn clock(seconds: u64) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Presenter {
+    pub next: RwSignal<String>,
+    pub notes: RwSignal<String>,
+}
+
+impl Presenter {
+    pub fn set(&self, EmittedPresenter { next, notes }: EmittedPresenter) {
+        self.next.set(next);
+        self.notes.set(notes);
+    }
+}
+
+impl Default for Presenter {
+    fn default() -> Self {
+        Presenter {
+            next: RwSignal::new(String::default()),
+            notes: RwSignal::new(String::default()),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use config::{EmittedOutline, OutlineHeading};
+use leptos::prelude::*;
+
+use super::markdown_preview::{follow_anchor, Markdown};
+
+/// the headings of the whole document, the one of the current section
+/// is highlighted and a click jumps to its slide.
+pub fn toc() -> impl IntoView {
+    let markdown = use_context::<Markdown>().unwrap();
+    let toc = use_context::<Toc>().unwrap();
+
+    // the last heading at or before the current slide
+    let current = move || {
+        let slide = markdown.current.get().saturating_sub(1);
+        toc.headings
+            .with(|x| x.iter().rposition(|x| x.slide <= slide))
+    };
+
+    let entries = move || {
+        toc.headings
+            .get()
+            .into_iter()
+            .enumerate()
+            .map(|(i, heading)| entry(heading, move || current() == Some(i), markdown))
+            .collect_view()
+    };
+
+    view! {
+        <Show when=move || toc.open.get()>
+            <aside class="fixed top-0 left-0 z-30 w-72 h-full overflow-auto bg-base-200 print:hidden">
+                <ul class="menu menu-sm">{entries}</ul>
+            </aside>
+        </Show>
+    }
+}
+
+fn entry(
+    OutlineHeading {
+        level, text, id, ..
+    }: OutlineHeading,
+    active: impl Fn() -> bool + Send + Sync + 'static,
+    markdown: Markdown,
+) -> impl IntoView {
+    let class = move || {
+        let active = if active() { "active" } else { "" };
+        format!("{} {active}", indent(level))
+    };
+    view! {
+        <li>
+            <a class=class on:click=move |_| follow_anchor(markdown, id.clone())>
+                {text}
+            </a>
+        </li>
+    }
+}
+
+fn indent(level: u8) -> &'static str {
+    match level {
+        1 => "pl-2 font-bold",
+        2 => "pl-4",
+        3 => "pl-8",
+        _ => "pl-12",
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Toc {
+    pub open: RwSignal<bool>,
+    pub headings: RwSignal<Vec<OutlineHeading>>,
+}
+
+impl Toc {
+    pub fn set(&self, EmittedOutline { headings }: EmittedOutline) {
+        self.headings.set(headings);
+    }
+
+    pub fn toggle(&self) {
+        self.open.update(|x| *x = !*x);
+    }
+}
+
+impl Default for Toc {
+    fn default() -> Self {
+        Toc {
+            open: RwSignal::new(false),
+            headings: RwSignal::new(Vec::new()),
+        }
+    }
+}
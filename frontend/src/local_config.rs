@@ -1,6 +1,9 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use config::{Action, EmittedConfig, InitConfig, KeyName};
+use config::{
+    Action, EmittedConfig, EmittedFrontMatter, FontSize, FrontMatter, HighlightCss, InitConfig,
+    KeyName,
+};
 use leptos::prelude::*;
 
 pub const THEMES_SIZE: usize = THEMES.len();
@@ -39,6 +42,36 @@ pub const THEMES: &[&str] = &[
     "sunset",
 ];
 
+/// themes that get the dark code highlighting palette
+pub const DARK_THEMES: &[&str] = &[
+    "dracula",
+    "synthwave",
+    "dark",
+    "halloween",
+    "forest",
+    "aqua",
+    "black",
+    "luxury",
+    "business",
+    "night",
+    "coffee",
+    "dim",
+    "sunset",
+];
+
+/// code highlighting css matching `theme`,
+/// printing always uses the light palette.
+pub fn code_css(theme: &str, css: &HighlightCss) -> String {
+    if DARK_THEMES.contains(&theme) {
+        format!(
+            "@media screen {{ {} }} @media print {{ {} }}",
+            css.dark, css.light
+        )
+    } else {
+        css.light.clone()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub theme_index: RwSignal<usize>,
@@ -47,7 +80,11 @@ pub struct Config {
     pub live_config_reload: Rc<RefCell<bool>>,
     pub keys: Rc<RefCell<HashMap<KeyName, Action>>>,
     pub keys_help: RwSignal<String>,
+    pub highlight_css: RwSignal<HighlightCss>,
     pub port: Rc<RefCell<u16>>,
+    pub default_theme: Rc<RefCell<String>>,
+    pub default_font_size: Rc<RefCell<FontSize>>,
+    pub front_matter: RwSignal<FrontMatter>,
 }
 
 impl Config {
@@ -56,31 +93,18 @@ impl Config {
         InitConfig {
             conf,
             keys_help,
+            highlight_css,
             port,
         }: InitConfig,
     ) {
-        let theme_index = THEMES
-            .iter()
-            .enumerate()
-            .find(|(_, x)| x.to_string() == conf.default_theme)
-            .map(|(i, _)| i)
-            .unwrap_or(0);
-        let font_size = match conf.default_font_size {
-            config::FontSize::VerySmall => "prose-sm".to_string(),
-            config::FontSize::Small => "prose-base".to_string(),
-            config::FontSize::Middle => "prose-lg".to_string(),
-            config::FontSize::Big => "prose-xl".to_string(),
-            config::FontSize::VeryBig => "prose-2xl".to_string(),
-        };
-        if theme_index != self.theme_index.get_untracked() {
-            self.theme_index.set(theme_index);
-        }
-        if font_size != self.font_size.get_untracked() {
-            self.font_size.set(font_size);
-        }
+        *self.default_theme.borrow_mut() = conf.default_theme;
+        *self.default_font_size.borrow_mut() = conf.default_font_size;
+        self.apply_theme();
+        self.apply_font_size();
         *self.theme_notification.borrow_mut() = conf.theme_notification;
         *self.keys.borrow_mut() = conf.keys.to_map();
         self.keys_help.set(keys_help);
+        self.highlight_css.set(highlight_css);
         *self.port.borrow_mut() = port;
     }
 
@@ -91,6 +115,7 @@ impl Config {
             live_config_reload,
             keys,
             keys_help,
+            highlight_css,
             port,
         }: EmittedConfig,
     ) {
@@ -98,9 +123,65 @@ impl Config {
         *self.live_config_reload.borrow_mut() = live_config_reload;
         *self.keys.borrow_mut() = keys.to_map();
         self.keys_help.set(keys_help);
+        if highlight_css != self.highlight_css.get_untracked() {
+            self.highlight_css.set(highlight_css);
+        }
         *self.port.borrow_mut() = port;
     }
 
+    /// the document front matter overrides the config theme and font size
+    pub fn set_front_matter(
+        &self,
+        EmittedFrontMatter {
+            front_matter,
+            highlight_css,
+        }: EmittedFrontMatter,
+    ) {
+        let old = self.front_matter.get_untracked();
+        self.front_matter.set(front_matter.clone());
+        if old.theme != front_matter.theme {
+            self.apply_theme();
+        }
+        if old.font_size != front_matter.font_size {
+            self.apply_font_size();
+        }
+        if highlight_css != self.highlight_css.get_untracked() {
+            self.highlight_css.set(highlight_css);
+        }
+    }
+
+    fn apply_theme(&self) {
+        let front_matter = self.front_matter.get_untracked();
+        let default_theme = self.default_theme.borrow();
+        let theme = front_matter.theme.as_ref().unwrap_or(&default_theme);
+        let theme_index = THEMES
+            .iter()
+            .position(|x| *x == theme.as_str())
+            .unwrap_or(0);
+        if theme_index != self.theme_index.get_untracked() {
+            self.theme_index.set(theme_index);
+        }
+    }
+
+    fn apply_font_size(&self) {
+        let front_matter = self.front_matter.get_untracked();
+        let default_font_size = self.default_font_size.borrow();
+        let font_size = match front_matter
+            .font_size
+            .as_ref()
+            .unwrap_or(&default_font_size)
+        {
+            FontSize::VerySmall => "prose-sm".to_string(),
+            FontSize::Small => "prose-base".to_string(),
+            FontSize::Middle => "prose-lg".to_string(),
+            FontSize::Big => "prose-xl".to_string(),
+            FontSize::VeryBig => "prose-2xl".to_string(),
+        };
+        if font_size != self.font_size.get_untracked() {
+            self.font_size.set(font_size);
+        }
+    }
+
     pub fn increase_font_size(&self) {
         self.font_size.update(|x| {
             *x = match x.as_str() {
@@ -146,7 +227,11 @@ impl Default for Config {
             live_config_reload: Rc::new(RefCell::new(true)),
             keys: Rc::new(RefCell::new(HashMap::new())),
             keys_help: RwSignal::new("".to_string()),
+            highlight_css: RwSignal::new(HighlightCss::default()),
             port: Rc::new(RefCell::new(80)),
+            default_theme: Rc::new(RefCell::new(THEMES[0].to_string())),
+            default_font_size: Rc::new(RefCell::new(FontSize::Small)),
+            front_matter: RwSignal::new(FrontMatter::default()),
         }
     }
 }
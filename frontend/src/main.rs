@@ -2,36 +2,74 @@ mod components;
 mod local_config;
 mod utils;
 
-use components::{help::help, markdown_preview::Markdown};
+use components::{
+    help::help,
+    markdown_preview::Markdown,
+    overview::{overview, Overview},
+    presenter::{presenter, Presenter},
+    toc::{toc, Toc},
+};
+use config::{FrontMatter, SlideSettings};
 use leptos::{
+    either::Either,
     html::{self},
     prelude::*,
+    spawn::spawn_local,
 };
-use local_config::{Config, THEMES, THEMES_SIZE};
+use local_config::{code_css, Config, DARK_THEMES, THEMES, THEMES_SIZE};
 use utils::{
-    config_init, key_bindings, listen_to_config, listen_to_markdown, notify, silent_invoke,
+    config_init, key_bindings, listen_to_config, listen_to_front_matter, listen_to_markdown,
+    listen_to_outline, listen_to_presenter, notify, presenter_mode, silent_invoke,
 };
 
 use crate::components::markdown_preview::markdown_preview;
 
-pub fn app() -> impl IntoView {
+/// the slides, or the speaker view when this is the presenter window
+pub fn app(presenter_window: bool) -> impl IntoView {
     let conf = Config::default();
     config_init(conf.clone());
     listen_to_config(conf.clone());
+    listen_to_front_matter(conf.clone());
 
     let markdown = Markdown::default();
     listen_to_markdown(markdown);
-    silent_invoke("md_init");
     provide_context(markdown);
+    if presenter_window {
+        let presenter = Presenter::default();
+        listen_to_presenter(presenter);
+        provide_context(presenter);
+    }
+    let slides_overview = Overview::default();
+    provide_context(slides_overview);
+    let outline = Toc::default();
+    listen_to_outline(outline);
+    provide_context(outline);
+    silent_invoke("md_init");
 
-    let theme = move || THEMES[conf.theme_index.get() % THEMES_SIZE];
-    let font_size = move || conf.font_size.get();
+    let conf_theme = move || THEMES[conf.theme_index.get() % THEMES_SIZE];
+    // a slide directive may pick another theme for its slide only
+    let theme = move || {
+        markdown
+            .settings
+            .with(|x| {
+                THEMES
+                    .iter()
+                    .find(|t| x.theme.as_deref() == Some(**t))
+                    .copied()
+            })
+            .unwrap_or_else(conf_theme)
+    };
+    let background = move || markdown.settings.with(slide_background);
+    let highlight_css = conf.highlight_css;
+    let highlight_style = move || highlight_css.with(|css| code_css(theme(), css));
+    let dark_theme = move || DARK_THEMES.contains(&theme());
+    let font_size = move || format!("min-h-screen {}", conf.font_size.get());
 
     Effect::new({
         let theme_notification = conf.theme_notification.clone();
         move |_| {
-            let theme = theme().to_string();
-            if *theme_notification.borrow() {
+            let theme = conf_theme().to_string();
+            if *theme_notification.borrow() && !presenter_window {
                 notify("iquity theme", theme);
             }
         }
@@ -39,31 +77,104 @@ pub fn app() -> impl IntoView {
 
     let keys_help = conf.keys_help;
     let port = conf.port.clone();
-    key_bindings(conf);
+    let front_matter = conf.front_matter;
+    let duration = move || front_matter.with(|x| x.duration);
+    key_bindings(conf, markdown, slides_overview, outline);
+
+    let view = if presenter_window {
+        Either::Left(presenter(port.clone(), dark_theme, duration))
+    } else {
+        Either::Right(markdown_preview(port.clone(), dark_theme))
+    };
 
     html::main()
         .attr("data-theme", theme)
         .class(font_size)
+        .style(background)
         .child((
-            markdown_preview(port),
+            html::style().child(highlight_style),
+            view,
+            overview(port, dark_theme),
+            toc(),
             help(keys_help),
+            (!presenter_window).then(|| byline(front_matter)),
             progress_bar(markdown),
         ))
 }
 
+fn slide_background(settings: &SlideSettings) -> String {
+    let color = settings
+        .background_color
+        .as_ref()
+        .map(|x| format!("background-color: {x};"));
+    let image = settings.background_image.as_ref().map(|x| {
+        format!(
+            "background-image: url('{}'); background-size: cover; background-position: center;",
+            css_url(x)
+        )
+    });
+    color.into_iter().chain(image).collect()
+}
+
+/// percent-encodes what could end the quoted url of a css declaration
+fn css_url(url: &str) -> String {
+    url.chars()
+        .map(|x| match x {
+            '\'' | '"' | '\\' | '(' | ')' | ';' => format!("%{:02X}", x as u32),
+            x if x.is_ascii_whitespace() || x.is_ascii_control() => format!("%{:02X}", x as u32),
+            x => x.to_string(),
+        })
+        .collect()
+}
+
+/// the author and the date of the front matter, in a corner of every slide
+fn byline(front_matter: RwSignal<FrontMatter>) -> impl IntoView {
+    let text = move || {
+        front_matter.with(|x| {
+            [&x.author, &x.date]
+                .into_iter()
+                .flatten()
+                .filter(|x| !x.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" · ")
+        })
+    };
+    view! {
+        <Show when=move || !text().is_empty()>
+            <footer class="fixed bottom-2 left-4 text-sm opacity-60 print:hidden">{text}</footer>
+        </Show>
+    }
+}
+
+/// the position across the sections at the bottom,
+/// and in the stack of sub slides on the right when there is one.
 fn progress_bar(markdown: Markdown) -> impl IntoView {
-    let max = move || markdown.len.get();
-    let value = move || markdown.current.get();
+    let max = move || markdown.stack.get().sections;
+    let value = move || markdown.stack.get().section;
+    let stacked = move || markdown.stack.get().len > 1;
+    let stack_height = move || {
+        let stack = markdown.stack.get();
+        format!("{}%", stack.current * 100 / stack.len.max(1))
+    };
     view! {
         <progress
             class="progress progress-success fixed bottom-0 h-1 w-full"
             value=value
             max=max
         />
+        <Show when=stacked>
+            <div class="fixed top-0 right-0 w-1 h-full bg-base-300">
+                <div class="w-full bg-success transition-all" style:height=stack_height />
+            </div>
+        </Show>
     }
 }
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
-    mount_to_body(app);
+    spawn_local(async {
+        let presenter_window = presenter_mode().await;
+        mount_to_body(move || app(presenter_window));
+    });
 }
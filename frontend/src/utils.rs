@@ -1,10 +1,16 @@
-use config::CONFIG_EVENT;
-use config::{Action, EmittedConfig, EmittedMarkdown, InitConfig, KeyName, CONTENT_EVENT};
+use config::{
+    Action, EmittedConfig, EmittedFrontMatter, EmittedMarkdown, InitConfig, KeyName, CONTENT_EVENT,
+};
+use config::{
+    EmittedOutline, EmittedPresenter, CONFIG_EVENT, FRONT_MATTER_EVENT, OUTLINE_EVENT,
+    PRESENTER_EVENT,
+};
 use futures::StreamExt;
 use gloo::utils::{document, window};
 use tauri_sys::{core::invoke, event::listen};
 
 use leptos::{ev, prelude::*, spawn::spawn_local};
+use std::cell::RefCell;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use wasm_bindgen::JsCast;
@@ -12,6 +18,9 @@ use wasm_bindgen::UnwrapThrowExt;
 use web_sys::HtmlDialogElement;
 
 use crate::components::help::HELP_ID;
+use crate::components::overview::Overview;
+use crate::components::presenter::Presenter;
+use crate::components::toc::Toc;
 use crate::{local_config::Config, Markdown};
 
 pub fn listen_to<F, T>(event: &'static str, fun: F)
@@ -49,6 +58,45 @@ pub fn config_init(conf: Config) {
     });
 }
 
+pub async fn slides_overview() -> Vec<String> {
+    invoke::<Vec<String>>("slides_overview", Empty {}).await
+}
+
+pub fn goto_slide(index: usize) {
+    #[derive(Serialize, Deserialize)]
+    struct Index {
+        index: usize,
+    }
+    spawn_local(async move {
+        invoke::<()>("goto_slide", Index { index }).await;
+    });
+}
+
+/// jumps to the slide holding the heading of the `anchor` id,
+/// returns whether there is one.
+pub async fn goto_anchor(anchor: String) -> bool {
+    #[derive(Serialize, Deserialize)]
+    struct Anchor {
+        anchor: String,
+    }
+    invoke::<bool>("goto_anchor", Anchor { anchor }).await
+}
+
+/// opens the markdown file at `path`, relative to the slides directory,
+/// returns whether it was opened.
+pub async fn open_markdown(path: String, anchor: Option<String>) -> bool {
+    #[derive(Serialize, Deserialize)]
+    struct Link {
+        path: String,
+        anchor: Option<String>,
+    }
+    invoke::<bool>("open_markdown", Link { path, anchor }).await
+}
+
+pub async fn presenter_mode() -> bool {
+    invoke::<bool>("presenter_mode", Empty {}).await
+}
+
 pub fn notify(title: &'static str, message: String) {
     #[derive(Serialize, Deserialize)]
     struct Content {
@@ -67,6 +115,27 @@ pub fn listen_to_markdown(markdown: Markdown) {
     });
 }
 
+pub fn listen_to_presenter(presenter: Presenter) {
+    listen_to(PRESENTER_EVENT, move |output: EmittedPresenter| {
+        presenter.set(output);
+        false
+    });
+}
+
+pub fn listen_to_outline(toc: Toc) {
+    listen_to(OUTLINE_EVENT, move |output: EmittedOutline| {
+        toc.set(output);
+        false
+    });
+}
+
+pub fn listen_to_front_matter(conf: Config) {
+    listen_to(FRONT_MATTER_EVENT, move |output: EmittedFrontMatter| {
+        conf.set_front_matter(output);
+        false
+    });
+}
+
 pub fn listen_to_config(conf: Config) {
     if *conf.live_config_reload.borrow() {
         listen_to(CONFIG_EVENT, move |output: EmittedConfig| {
@@ -77,13 +146,50 @@ pub fn listen_to_config(conf: Config) {
     }
 }
 
-pub fn key_bindings(conf: Config) {
+/// the config name of the pressed key, the browser codes of the arrows and the other
+/// named keys differ from the GLFW like ones of `KeyName`, unlike letters and signs
+fn key_name(ke: &ev::KeyboardEvent) -> KeyName {
+    match ke.key().as_str() {
+        "ArrowUp" => KeyName::Up,
+        "ArrowDown" => KeyName::Down,
+        "ArrowLeft" => KeyName::Left,
+        "ArrowRight" => KeyName::Right,
+        "Escape" => KeyName::Escape,
+        "Enter" => KeyName::Enter,
+        "Tab" => KeyName::Tab,
+        "Backspace" => KeyName::Backspace,
+        "Insert" => KeyName::Insert,
+        "Delete" => KeyName::Delete,
+        "PageUp" => KeyName::PageUp,
+        "PageDown" => KeyName::PageDown,
+        "Home" => KeyName::Home,
+        "End" => KeyName::End,
+        _ => KeyName::from(ke.key_code() as u16),
+    }
+}
+
+pub fn key_bindings(conf: Config, markdown: Markdown, overview: Overview, toc: Toc) {
+    // the slide number typed so far, vim like
+    let prefix = RefCell::new(String::new());
     window_event_listener(ev::keydown, move |ke: ev::KeyboardEvent| {
+        if overview.open.get_untracked() && overview_keys(&ke, overview) {
+            return;
+        }
         let keys = conf.keys.borrow();
         log!("{}", ke.key_code());
-        let Some(action) = keys.get(&KeyName::from(ke.key_code() as u16)) else {
+        let key = ke.key();
+        let Some(action) = keys.get(&key_name(&ke)) else {
+            if key.chars().all(|x| x.is_ascii_digit()) {
+                prefix.borrow_mut().push_str(&key);
+                return;
+            }
+            let number = prefix.take().parse::<usize>().ok();
+            if let (Some(number), "Enter") = (number, key.as_str()) {
+                goto_slide(number.saturating_sub(1));
+            }
             return;
         };
+        let number = prefix.take().parse::<usize>().ok();
 
         match action {
             Action::Print => window().print().unwrap_throw(),
@@ -93,6 +199,19 @@ pub fn key_bindings(conf: Config) {
             Action::PrevSlide => silent_invoke("prev_slide"),
             Action::IncreaseFontsize => conf.increase_font_size(),
             Action::DecreaseFontsize => conf.decrease_font_size(),
+            Action::Presenter => silent_invoke("open_presenter"),
+            Action::SlideUp => silent_invoke("slide_up"),
+            Action::SlideDown => silent_invoke("slide_down"),
+            Action::Overview => overview.toggle(markdown.current.get_untracked().saturating_sub(1)),
+            Action::FirstSlide => silent_invoke("first_slide"),
+            Action::LastSlide => silent_invoke("last_slide"),
+            Action::Toc => toc.toggle(),
+            Action::HistoryBack => silent_invoke("history_back"),
+            Action::HistoryForward => silent_invoke("history_forward"),
+            Action::GotoSlide => match number {
+                Some(number) => goto_slide(number.saturating_sub(1)),
+                None => silent_invoke("last_slide"),
+            },
             Action::Help => {
                 let dialog: HtmlDialogElement = document()
                     .get_element_by_id(HELP_ID)
@@ -104,3 +223,19 @@ pub fn key_bindings(conf: Config) {
         }
     });
 }
+
+/// the arrows move the selection of the open overview, Enter jumps to it
+/// and Escape closes it, returns whether the key was one of them.
+fn overview_keys(ke: &ev::KeyboardEvent, overview: Overview) -> bool {
+    match ke.key().as_str() {
+        "ArrowLeft" => overview.select(-1, false),
+        "ArrowRight" => overview.select(1, false),
+        "ArrowUp" => overview.select(-1, true),
+        "ArrowDown" => overview.select(1, true),
+        "Enter" => overview.jump(overview.selected.get_untracked()),
+        "Escape" => overview.open.set(false),
+        _ => return false,
+    }
+    ke.prevent_default();
+    true
+}
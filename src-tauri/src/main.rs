@@ -1,8 +1,14 @@
 use axum::Router;
-use config::{GlobalConfig, InitConfig};
-use tauri::{generate_context, App, AppHandle, Manager};
+use config::{FrontMatter, GlobalConfig, InitConfig};
+use tauri::{
+    generate_context, App, AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
 use tauri_plugin_notification::NotificationExt;
-use utils::{emit_markdown, markdown_compile, read_markdown};
+use utils::{
+    contained_path, document_highlight_css, emit_front_matter, emit_markdown, emit_outline,
+    markdown_compile, open_document, read_markdown, set_code_aliases, Document, Slide, SlidesCache,
+    PRESENTER_WINDOW,
+};
 
 use std::{
     io::{stdout, Write},
@@ -12,12 +18,11 @@ use std::{
     sync::Mutex,
 };
 use tauri_plugin_cli::CliExt;
+use tokio::sync::Notify;
 use tower_http::services::ServeDir;
 
 mod utils;
 
-const SLIDES_SPLITTER: &str = "\n---";
-
 const HELP_MESSAGE: &[u8] = r#"
     Welcom to iquity 
         the markdown previewer
@@ -33,16 +38,48 @@ const HELP_MESSAGE: &[u8] = r#"
 
 struct BackendContext {
     port: u16,
-    slides_path: PathBuf,
+    slides_path: Mutex<PathBuf>,
     slides_home_path: PathBuf,
-    slides: Mutex<Vec<String>>,
+    slides: Mutex<Vec<Slide>>,
+    slides_cache: SlidesCache,
     slide_index: Mutex<usize>,
+    fragment_index: Mutex<usize>,
     config_path: PathBuf,
-    config: GlobalConfig,
+    config: Mutex<GlobalConfig>,
+    front_matter: Mutex<FrontMatter>,
+    dependencies: Mutex<Vec<PathBuf>>,
+    history: Mutex<History>,
+    document_opened: Notify,
+}
+
+/// a document and the slide it was left on
+#[derive(Clone)]
+struct Visit {
+    path: PathBuf,
+    slide: usize,
+    fragment: usize,
+}
+
+/// the documents opened through links before and after the current one
+#[derive(Default)]
+struct History {
+    back: Vec<Visit>,
+    forward: Vec<Visit>,
+}
+
+impl History {
+    /// the stack going `back` or forward takes from, and the one it gives to
+    fn stacks(&mut self, back: bool) -> (&mut Vec<Visit>, &mut Vec<Visit>) {
+        if back {
+            (&mut self.back, &mut self.forward)
+        } else {
+            (&mut self.forward, &mut self.back)
+        }
+    }
 }
 
 impl BackendContext {
-    async fn new(path: PathBuf, port: u16) -> Result<Self, String> {
+    async fn new(path: PathBuf, port: u16, slide: usize) -> Result<Self, String> {
         let (markdown_path, markdown_parent_path) = if path.is_file() {
             (path.clone(), path.parent().unwrap().into())
         } else if path.is_dir() {
@@ -57,10 +94,6 @@ impl BackendContext {
             return Err("provided path does not exist".to_string());
         };
 
-        let slides = read_markdown(&markdown_path)
-            .await
-            .map_err(|x| x.to_string())?;
-
         let config_path = GlobalConfig::config_path().unwrap();
         let config = match GlobalConfig::get(&config_path).await {
             Ok(conf) => conf,
@@ -69,18 +102,56 @@ impl BackendContext {
                 GlobalConfig::default()
             }
         };
+        set_code_aliases(&config.code_aliases);
+
+        let slides_cache = SlidesCache::default();
+        let Document {
+            front_matter,
+            slides,
+            dependencies,
+            warnings,
+        } = read_markdown(
+            &markdown_path,
+            &markdown_parent_path,
+            &slides_cache,
+            &config,
+        )
+        .await
+        .map_err(|x| x.to_string())?;
+        for warning in warnings {
+            eprintln!("slides warning : {warning}");
+        }
+        let front_matter = front_matter.unwrap_or_else(|err| {
+            eprintln!("front matter error : {}", err);
+            FrontMatter::default()
+        });
+        let slide_index = slide.saturating_sub(1).min(slides.len().saturating_sub(1));
 
         Ok(BackendContext {
-            slides_path: markdown_path,
+            slides_path: Mutex::new(markdown_path),
             slides_home_path: markdown_parent_path,
             config_path,
             port,
             slides: Mutex::new(slides),
-            slide_index: Mutex::new(0),
-            config,
+            slides_cache,
+            slide_index: Mutex::new(slide_index),
+            fragment_index: Mutex::new(0),
+            config: Mutex::new(config),
+            front_matter: Mutex::new(front_matter),
+            dependencies: Mutex::new(dependencies),
+            history: Mutex::new(History::default()),
+            document_opened: Notify::new(),
         })
     }
 
+    fn visit(&self) -> Visit {
+        Visit {
+            path: self.slides_path.lock().unwrap().clone(),
+            slide: *self.slide_index.lock().unwrap(),
+            fragment: *self.fragment_index.lock().unwrap(),
+        }
+    }
+
     fn serve_assets(&self) {
         let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
         let app = Router::new().nest_service("/", ServeDir::new(&self.slides_home_path));
@@ -101,7 +172,23 @@ async fn main() {
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_cli::init())
         .invoke_handler(tauri::generate_handler![
-            conf_init, md_init, next_slide, prev_slide, notify,
+            conf_init,
+            md_init,
+            next_slide,
+            prev_slide,
+            slide_up,
+            slide_down,
+            first_slide,
+            last_slide,
+            goto_slide,
+            goto_anchor,
+            slides_overview,
+            open_markdown,
+            history_back,
+            history_forward,
+            notify,
+            open_presenter,
+            presenter_mode,
         ])
         .setup(setup)
         .run(generate_context!())
@@ -118,13 +205,26 @@ fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
         stdout().write_all(HELP_MESSAGE)?;
         std::process::exit(0x0100);
     };
+    let slide = matches
+        .args
+        .get("slide")
+        .and_then(|x| x.value.as_str().and_then(|x| x.parse::<usize>().ok()))
+        .unwrap_or(1);
 
     let app_handle_1 = app.app_handle().clone();
     tokio::task::spawn(async move {
+        if let Some(config_dir) =
+            GlobalConfig::config_path().and_then(|x| x.parent().map(PathBuf::from))
+        {
+            utils::load_user_definitions(&app_handle_1, &config_dir);
+        }
         let port = portpicker::pick_unused_port().unwrap();
-        let context = BackendContext::new(markdown_path, port).await.unwrap();
+        let context = BackendContext::new(markdown_path, port, slide)
+            .await
+            .unwrap();
         context.serve_assets();
         app_handle_1.manage(context);
+        utils::set_window_title(&app_handle_1);
         let app_handle_2 = app_handle_1.clone();
         let app_handle_3 = app_handle_1.clone();
 
@@ -146,19 +246,25 @@ fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
 #[tauri::command]
 fn md_init(app: AppHandle) {
     let context = app.state::<BackendContext>();
+    emit_front_matter(&app);
     let slides = context.slides.lock().unwrap();
-    emit_markdown(&app, 0, slides.len(), &slides[0]);
+    let index = context.slide_index.lock().unwrap();
+    let fragment = context.fragment_index.lock().unwrap();
+    emit_markdown(&app, *index, *fragment, &slides);
+    emit_outline(&app, &slides);
 }
 
 #[tauri::command]
 fn conf_init(app: AppHandle) -> InitConfig {
     let context = app.state::<BackendContext>();
-    let conf = context.config.clone();
+    let conf = context.config.lock().unwrap().clone();
     let keys_help = markdown_compile(conf.keys.to_string());
+    let highlight_css = document_highlight_css(&app);
 
     InitConfig {
         conf,
         keys_help,
+        highlight_css,
         port: context.port,
     }
 }
@@ -181,13 +287,15 @@ fn next_slide(app: AppHandle) {
     let context = app.state::<BackendContext>();
     let slides = context.slides.lock().unwrap();
     let mut index = context.slide_index.lock().unwrap();
-    let slide = if *index < slides.len() - 1 {
-        *index += 1;
-        slides.get(*index).unwrap()
-    } else {
-        slides.last().unwrap()
-    };
-    emit_markdown(&app, *index, slides.len(), slide);
+    let mut fragment = context.fragment_index.lock().unwrap();
+    let section = slides[*index].section;
+    if *fragment < slides[*index].fragments {
+        *fragment += 1;
+    } else if let Some(next) = slides.iter().position(|x| x.section > section) {
+        *index = next;
+        *fragment = 0;
+    }
+    emit_markdown(&app, *index, *fragment, &slides);
 }
 
 #[tauri::command]
@@ -195,7 +303,182 @@ fn prev_slide(app: AppHandle) {
     let context = app.state::<BackendContext>();
     let slides = context.slides.lock().unwrap();
     let mut index = context.slide_index.lock().unwrap();
-    *index = index.checked_sub(1).unwrap_or(0);
-    let slide = slides.get(*index).unwrap();
-    emit_markdown(&app, *index, slides.len(), slide);
+    let mut fragment = context.fragment_index.lock().unwrap();
+    let section = slides[*index].section;
+    if *fragment > 0 {
+        *fragment -= 1;
+    } else if section > 0 {
+        // coming back to a slide shows it fully revealed
+        *index = slides
+            .iter()
+            .position(|x| x.section == section - 1)
+            .unwrap();
+        *fragment = slides[*index].fragments;
+    }
+    emit_markdown(&app, *index, *fragment, &slides);
+}
+
+/// moves down the vertical stack of sub slides of the current section
+#[tauri::command]
+fn slide_down(app: AppHandle) {
+    let context = app.state::<BackendContext>();
+    let slides = context.slides.lock().unwrap();
+    let mut index = context.slide_index.lock().unwrap();
+    let mut fragment = context.fragment_index.lock().unwrap();
+    let section = slides[*index].section;
+    if slides.get(*index + 1).is_some_and(|x| x.section == section) {
+        *index += 1;
+        *fragment = 0;
+    }
+    emit_markdown(&app, *index, *fragment, &slides);
+}
+
+#[tauri::command]
+fn slide_up(app: AppHandle) {
+    let context = app.state::<BackendContext>();
+    let slides = context.slides.lock().unwrap();
+    let mut index = context.slide_index.lock().unwrap();
+    let mut fragment = context.fragment_index.lock().unwrap();
+    let section = slides[*index].section;
+    if *index > 0 && slides[*index - 1].section == section {
+        *index -= 1;
+        *fragment = slides[*index].fragments;
+    }
+    emit_markdown(&app, *index, *fragment, &slides);
+}
+
+#[tauri::command]
+fn first_slide(app: AppHandle) {
+    goto_slide(app, 0);
+}
+
+#[tauri::command]
+fn last_slide(app: AppHandle) {
+    goto_slide(app, usize::MAX);
+}
+
+/// jumps to the slide at `index` of all the slides, nothing revealed yet
+#[tauri::command]
+fn goto_slide(app: AppHandle, index: usize) {
+    let context = app.state::<BackendContext>();
+    let slides = context.slides.lock().unwrap();
+    let mut current = context.slide_index.lock().unwrap();
+    let mut fragment = context.fragment_index.lock().unwrap();
+    *current = index.min(slides.len().saturating_sub(1));
+    *fragment = 0;
+    emit_markdown(&app, *current, *fragment, &slides);
+}
+
+/// jumps to the slide holding the heading with the `anchor` id,
+/// returns whether there is one.
+#[tauri::command]
+fn goto_anchor(app: AppHandle, anchor: String) -> bool {
+    let index = {
+        let context = app.state::<BackendContext>();
+        let slides = context.slides.lock().unwrap();
+        slides
+            .iter()
+            .position(|x| x.headings.iter().any(|x| x.id == anchor))
+    };
+    if let Some(index) = index {
+        goto_slide(app, index);
+    }
+    index.is_some()
+}
+
+/// the content of every slide, for the overview grid
+#[tauri::command]
+fn slides_overview(app: AppHandle) -> Vec<String> {
+    let context = app.state::<BackendContext>();
+    let slides = context.slides.lock().unwrap();
+    slides.iter().map(|x| x.content.clone()).collect()
+}
+
+/// opens the markdown file at `path`, relative to the slides directory,
+/// on the heading of `anchor` if there is one, returns whether it was opened.
+#[tauri::command]
+async fn open_markdown(app: AppHandle, path: String, anchor: Option<String>) -> bool {
+    let context = app.state::<BackendContext>();
+    let target = context
+        .slides_home_path
+        .canonicalize()
+        .ok()
+        .and_then(|home| contained_path(&home, &home.join(&path)));
+    let target = match target {
+        Some(target) if target.is_file() => target,
+        _ => {
+            let message = format!("can not open {path} out of the slides directory");
+            message_notify(&app, "Link Error", &message);
+            return false;
+        }
+    };
+    let visit = context.visit();
+    if let Err(err) = open_document(&app, target, 0, 0).await {
+        message_notify(&app, "Link Error", &err.to_string());
+        return false;
+    }
+    {
+        let mut history = context.history.lock().unwrap();
+        history.back.push(visit);
+        history.forward.clear();
+    }
+    if let Some(anchor) = anchor {
+        goto_anchor(app.clone(), anchor);
+    }
+    true
+}
+
+#[tauri::command]
+async fn history_back(app: AppHandle) {
+    travel_history(&app, true).await;
+}
+
+#[tauri::command]
+async fn history_forward(app: AppHandle) {
+    travel_history(&app, false).await;
+}
+
+/// goes back to the previous document, or forward to the next one,
+/// on the slide it was left on.
+async fn travel_history(app: &AppHandle, back: bool) {
+    let context = app.state::<BackendContext>();
+    let current = context.visit();
+    let visit = {
+        let mut history = context.history.lock().unwrap();
+        let (from, _) = history.stacks(back);
+        let Some(visit) = from.last().cloned() else {
+            return;
+        };
+        visit
+    };
+    // the history only moves once the document could be opened
+    if let Err(err) = open_document(app, visit.path, visit.slide, visit.fragment).await {
+        message_notify(app, "History Error", &err.to_string());
+        return;
+    }
+    let mut history = context.history.lock().unwrap();
+    let (from, to) = history.stacks(back);
+    from.pop();
+    to.push(current);
+}
+
+/// opens the presenter window, or focuses it when it is already open
+#[tauri::command]
+async fn open_presenter(app: AppHandle) {
+    if let Some(window) = app.get_webview_window(PRESENTER_WINDOW) {
+        window.set_focus().unwrap_or_default();
+        return;
+    }
+    let window = WebviewWindowBuilder::new(&app, PRESENTER_WINDOW, WebviewUrl::default())
+        .title("iquity presenter")
+        .inner_size(1000.0, 700.0)
+        .build();
+    if let Err(err) = window {
+        message_notify(&app, "Presenter Window Error", &err.to_string());
+    }
+}
+
+#[tauri::command]
+fn presenter_mode(window: WebviewWindow) -> bool {
+    window.label() == PRESENTER_WINDOW
 }
@@ -1,24 +1,86 @@
 use crate::{message_notify, BackendContext};
 
-use super::SLIDES_SPLITTER;
-
-use config::{EmittedConfig, EmittedMarkdown, GlobalConfig, CONFIG_EVENT, CONTENT_EVENT};
+use config::{
+    EmittedConfig, EmittedFrontMatter, EmittedMarkdown, EmittedOutline, EmittedPresenter,
+    FrontMatter, GlobalConfig, HighlightCss, OutlineHeading, SlideSettings, SlideSplit, Stack,
+    CONFIG_EVENT, CONTENT_EVENT, FRONT_MATTER_EVENT, OUTLINE_EVENT, PRESENTER_EVENT,
+};
 use futures::{
     channel::mpsc::{channel, Receiver},
     SinkExt, StreamExt,
 };
 use notify::{
-    event::ModifyKind, Config, Event, EventKind::Modify, RecommendedWatcher, RecursiveMode, Watcher,
+    event::ModifyKind,
+    Config, Event,
+    EventKind::{self, Modify},
+    RecommendedWatcher, RecursiveMode, Watcher,
 };
 
 use tauri::{AppHandle, Emitter, Manager};
 
-use markdown::{self, CompileOptions, Options, ParseOptions};
+use markdown::{
+    self,
+    mdast::{Node, Root},
+    to_mdast, CompileOptions, Constructs, Options, ParseOptions,
+};
 use rayon::prelude::*;
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 mod code_syntax_highlight;
-use code_syntax_highlight::code_syntax_highlight;
+mod diagram_render;
+pub use code_syntax_highlight::set_code_aliases;
+use code_syntax_highlight::{
+    code_syntax_highlight, highlight_css, is_user_definition, user_definition_dirs,
+};
+
+mod math_render;
+use math_render::math_render;
+
+mod slides_split;
+use slides_split::{slides_split, sub_slides_split};
+
+mod definitions;
+use definitions::Definitions;
+
+mod front_matter;
+use front_matter::front_matter;
+
+mod include;
+use include::expand_includes;
+
+mod code_embed;
+
+mod speaker_notes;
+use speaker_notes::split_notes;
+
+mod fragments;
+use fragments::fragments_render;
+
+mod slide_directive;
+use slide_directive::slide_directive;
+
+mod containers;
+use containers::containers_render;
+
+mod headings;
+pub use headings::Heading;
+use headings::{heading_markers, headings_render, Slugger};
+
+mod toc;
+use toc::{toc_markers, toc_render};
+
+mod placeholders;
+use placeholders::Placeholders;
+
+pub const MAIN_WINDOW: &str = "main";
+pub const PRESENTER_WINDOW: &str = "presenter";
+const APP_TITLE: &str = "iquity";
 
 fn watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<Event>>)> {
     let (mut tx, rx) = channel(1);
@@ -40,47 +102,200 @@ fn watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<Even
 pub async fn watch_markdown(app: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let (mut watcher, mut rx) = watcher()?;
     let context = app.state::<BackendContext>();
-    let path = context.slides_path.clone();
     let parent = &context.slides_home_path;
     watcher.watch(parent.as_path(), RecursiveMode::NonRecursive)?;
+    let mut watched_dirs = Vec::new();
+    watch_dependencies(&mut watcher, &mut watched_dirs, &app)?;
 
     loop {
-        let Some(Ok(ev)) = rx.next().await else {
-            continue;
-        };
-        let Modify(ModifyKind::Data(_)) = ev.kind else {
-            continue;
-        };
-        let slides = read_markdown(&path).await?;
+        tokio::select! {
+            ev = rx.next() => {
+                let Some(Ok(ev)) = ev else {
+                    continue;
+                };
+                let Modify(ModifyKind::Data(_)) = ev.kind else {
+                    continue;
+                };
+                // a file missing for a moment, like during an atomic save,
+                // must not stop the live reload
+                notify_refresh(&app).await;
+            }
+            _ = context.document_opened.notified() => (),
+        }
+        if let Err(err) = watch_dependencies(&mut watcher, &mut watched_dirs, &app) {
+            message_notify(&app, "Slides Watch Error", &err.to_string());
+        }
+    }
+}
+
+/// keeps the directories of the opened document and of the files it
+/// depends on watched, the slides directory itself is watched for the whole session.
+fn watch_dependencies(
+    watcher: &mut RecommendedWatcher,
+    watched_dirs: &mut Vec<PathBuf>,
+    app: &AppHandle,
+) -> notify::Result<()> {
+    let context = app.state::<BackendContext>();
+    let home = context.slides_home_path.canonicalize()?;
+    let document = context.slides_path.lock().unwrap().canonicalize()?;
+    let mut dirs = context
+        .dependencies
+        .lock()
+        .unwrap()
+        .iter()
+        .chain([&document])
+        .filter_map(|x| x.parent().map(PathBuf::from))
+        .filter(|x| *x != home)
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs.dedup();
+
+    for dir in watched_dirs.iter().filter(|x| !dirs.contains(x)) {
+        // the directory may be gone already
+        let _ = watcher.unwatch(dir);
+    }
+    for dir in dirs.iter().filter(|x| !watched_dirs.contains(x)) {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    *watched_dirs = dirs;
+    Ok(())
+}
+
+fn notify_warnings(app: &AppHandle, warnings: &[String]) {
+    if !warnings.is_empty() {
+        message_notify(app, "Slides Warning", &warnings.join("\n"));
+    }
+}
+
+/// rereads the slides and emits the current one if it or the next one changed
+pub async fn refresh_slides(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let context = app.state::<BackendContext>();
+    let config = context.config.lock().unwrap().clone();
+    let path = context.slides_path.lock().unwrap().clone();
+    let Document {
+        front_matter,
+        slides,
+        dependencies,
+        warnings,
+    } = read_markdown(
+        &path,
+        &context.slides_home_path,
+        &context.slides_cache,
+        &config,
+    )
+    .await?;
+    notify_warnings(app, &warnings);
+    *context.dependencies.lock().unwrap() = dependencies;
+    match front_matter {
+        Ok(front_matter) => set_front_matter(app, front_matter),
+        Err(err) => message_notify(app, "Front Matter Error", &err),
+    }
+    let mut content_slides = context.slides.lock().unwrap();
+    let mut index = context.slide_index.lock().unwrap();
+    let mut fragment = context.fragment_index.lock().unwrap();
+    let old_len = content_slides.len();
+    let shown = |slides: &[Slide], index: usize| {
+        slides
+            .iter()
+            .skip(index)
+            .take(2)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let old_slides = shown(&content_slides, *index);
+    if document_outline(&content_slides) != document_outline(&slides) {
+        emit_outline(app, &slides);
+    }
+    *content_slides = slides;
+    if *index > content_slides.len() - 1 {
+        *index = content_slides.len() - 1;
+    };
+    *fragment = (*fragment).min(content_slides[*index].fragments);
+    if old_len == content_slides.len() && old_slides == shown(&content_slides, *index) {
+        return Ok(());
+    }
+    emit_markdown(app, *index, *fragment, &content_slides);
+    Ok(())
+}
+
+/// shows the document at `path` in place of the current one, from the slide
+/// at `index` with `fragment` fragments revealed, and re-targets the watcher.
+pub async fn open_document(
+    app: &AppHandle,
+    path: PathBuf,
+    index: usize,
+    fragment: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let context = app.state::<BackendContext>();
+    let config = context.config.lock().unwrap().clone();
+    let Document {
+        front_matter,
+        slides,
+        dependencies,
+        warnings,
+    } = read_markdown(
+        &path,
+        &context.slides_home_path,
+        &context.slides_cache,
+        &config,
+    )
+    .await?;
+    notify_warnings(app, &warnings);
+    *context.slides_path.lock().unwrap() = path;
+    *context.dependencies.lock().unwrap() = dependencies;
+    set_front_matter(
+        app,
+        front_matter.unwrap_or_else(|err| {
+            message_notify(app, "Front Matter Error", &err);
+            FrontMatter::default()
+        }),
+    );
+    {
         let mut content_slides = context.slides.lock().unwrap();
+        let mut current = context.slide_index.lock().unwrap();
+        let mut current_fragment = context.fragment_index.lock().unwrap();
         *content_slides = slides;
-        let mut index = context.slide_index.lock().unwrap();
-        if *index > content_slides.len() - 1 {
-            *index = content_slides.len() - 1;
-        };
-        emit_markdown(
-            &app,
-            *index,
-            content_slides.len(),
-            content_slides.get(*index).unwrap_or(&String::new()),
-        );
+        *current = index.min(content_slides.len() - 1);
+        *current_fragment = fragment.min(content_slides[*current].fragments);
+        emit_markdown(app, *current, *current_fragment, &content_slides);
+        emit_outline(app, &content_slides);
     }
+    context.document_opened.notify_one();
+    Ok(())
 }
 
 pub async fn watch_config(app: AppHandle, port: u16) -> Result<(), Box<dyn std::error::Error>> {
     let (mut watcher, mut rx) = watcher()?;
-    let path = &app.state::<BackendContext>().config_path;
+    let context = app.state::<BackendContext>();
+    let path = &context.config_path;
     let watch_path = path.parent().unwrap();
 
+    // the user definition directories may only be created later on
+    let definition_dirs = user_definition_dirs(watch_path);
     watcher.watch(watch_path, RecursiveMode::NonRecursive)?;
+    for dir in definition_dirs.iter().filter(|x| x.is_dir()) {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
 
     loop {
         let Some(Ok(ev)) = rx.next().await else {
             continue;
         };
-        let Modify(ModifyKind::Data(_)) = ev.kind else {
+        if let EventKind::Create(_) = ev.kind {
+            for dir in ev.paths.iter().filter(|x| definition_dirs.contains(x)) {
+                let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+            }
+        }
+        if ev.paths.iter().any(|x| is_user_definition(x)) {
+            if let EventKind::Access(_) = ev.kind {
+                continue;
+            }
+            load_user_definitions(&app, watch_path);
+            context.slides_cache.clear();
+            notify_refresh(&app).await;
+        } else if !matches!(ev.kind, Modify(ModifyKind::Data(_))) {
             continue;
-        };
+        }
 
         let global_config = match GlobalConfig::get(&path).await {
             Ok(gc) => gc,
@@ -91,8 +306,24 @@ pub async fn watch_config(app: AppHandle, port: u16) -> Result<(), Box<dyn std::
         };
         let lch = global_config.live_config_reload;
 
+        let aliases_changed = set_code_aliases(&global_config.code_aliases);
+        if aliases_changed {
+            context.slides_cache.clear();
+        }
+        let split_changed = {
+            let mut config = context.config.lock().unwrap();
+            let split_changed = config.slide_split != global_config.slide_split
+                || config.sub_slide_split != global_config.sub_slide_split;
+            *config = global_config.clone();
+            split_changed
+        };
+        if aliases_changed || split_changed {
+            notify_refresh(&app).await;
+        }
+
         let keys_help = markdown_compile(global_config.keys.to_string());
-        let emitted_config = EmittedConfig::new(global_config, keys_help, port);
+        let highlight_css = document_highlight_css(&app);
+        let emitted_config = EmittedConfig::new(global_config, keys_help, highlight_css, port);
         emit_config(&app, emitted_config);
         if !lch {
             break;
@@ -101,18 +332,256 @@ pub async fn watch_config(app: AppHandle, port: u16) -> Result<(), Box<dyn std::
     Ok(())
 }
 
+/// refreshes the slides for a config change, a failure is reported
+/// without stopping the config watcher.
+async fn notify_refresh(app: &AppHandle) {
+    if let Err(err) = refresh_slides(app).await {
+        message_notify(app, "Slides Refresh Error", &err.to_string());
+    }
+}
+
+/// loads the user syntaxes and themes living next to the config file
+pub fn load_user_definitions(app: &AppHandle, config_dir: &Path) {
+    for err in code_syntax_highlight::load_user_definitions(config_dir) {
+        message_notify(app, "Syntax Definition Error", &err);
+    }
+}
+
+/// stores the front matter of the document if it changed,
+/// then renames the window and sends the new settings to the frontend.
+pub fn set_front_matter(app: &AppHandle, front_matter: FrontMatter) {
+    let context = app.state::<BackendContext>();
+    {
+        let mut current = context.front_matter.lock().unwrap();
+        if *current == front_matter {
+            return;
+        }
+        *current = front_matter;
+    }
+    set_window_title(app);
+    emit_front_matter(app);
+}
+
+pub fn set_window_title(app: &AppHandle) {
+    let context = app.state::<BackendContext>();
+    let title = context.front_matter.lock().unwrap().title.clone();
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        window
+            .set_title(title.as_deref().unwrap_or(APP_TITLE))
+            .unwrap_or_default();
+    }
+}
+
+/// the code highlighting css of the config themes or of the front matter one
+pub fn document_highlight_css(app: &AppHandle) -> HighlightCss {
+    let context = app.state::<BackendContext>();
+    let themes = context.config.lock().unwrap().highlight_themes.clone();
+    let themes = context
+        .front_matter
+        .lock()
+        .unwrap()
+        .highlight_themes(&themes);
+    highlight_css(&themes)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Slide {
+    pub content: String,
+    pub notes: String,
+    pub fragments: usize,
+    /// the vertical stack of the slide and its place in it
+    pub section: usize,
+    pub sub: usize,
+    pub settings: SlideSettings,
+    /// the headings of the slide, their ids are unique across the document
+    pub headings: Vec<Heading>,
+}
+
+pub struct Document {
+    pub front_matter: Result<FrontMatter, String>,
+    pub slides: Vec<Slide>,
+    /// the included and embedded files
+    pub dependencies: Vec<PathBuf>,
+    /// what the document asks for that can not be done, for the user to be told
+    pub warnings: Vec<String>,
+}
+
 pub async fn read_markdown<P: AsRef<Path>>(
     path: P,
-) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let text = tokio::fs::read_to_string(path).await?;
-    let slides = text
-        .split(SLIDES_SPLITTER)
-        .collect::<Vec<_>>()
-        .into_par_iter()
-        .map(code_syntax_highlight)
-        .map(markdown_compile)
-        .collect();
-    Ok(slides)
+    root_dir: &Path,
+    cache: &SlidesCache,
+    config: &GlobalConfig,
+) -> Result<Document, Box<dyn std::error::Error>> {
+    let text = tokio::fs::read_to_string(&path).await?;
+    let mut dependencies = Vec::new();
+    let mut root = markdown_parse(&text);
+    // only a document that includes or embeds other files is parsed again
+    let text = match expand_includes(&text, &root, path.as_ref(), root_dir, &mut dependencies) {
+        Some(expanded) => {
+            root = markdown_parse(&expanded);
+            expanded
+        }
+        None => text,
+    };
+    let front_matter = front_matter(&root);
+    let mut warnings = Vec::new();
+    // an invalid split of the front matter falls back to the config one
+    let mut document_split = |key: &str, split: Option<&String>, default: &SlideSplit| {
+        let Some(split) = split else {
+            return default.clone();
+        };
+        split.parse().unwrap_or_else(|err| {
+            warnings.push(format!("front matter {key} : {err}"));
+            default.clone()
+        })
+    };
+    let settings = front_matter.as_ref().ok();
+    let split = document_split(
+        "slide_split",
+        settings.and_then(|x| x.slide_split.as_ref()),
+        &config.slide_split,
+    );
+    let sub_split = document_split(
+        "sub_slide_split",
+        settings.and_then(|x| x.sub_slide_split.as_ref()),
+        &config.sub_slide_split,
+    );
+    let incremental = settings.is_some_and(|x| x.incremental_lists);
+
+    let definitions = Definitions::new(&text, &root);
+    let nodes = root.children().map(Vec::as_slice).unwrap_or_default();
+    let (sources, details): (Vec<_>, Vec<_>) = slides_split(&text, nodes, &split)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(section, slide)| {
+            sub_slides_split(&text, slide, &sub_split)
+                .into_iter()
+                .enumerate()
+                .map(move |(sub, slide)| (section, sub, slide))
+        })
+        .enumerate()
+        .map(|(index, (section, sub, slide))| {
+            let (settings, mut edits, slide_warnings) = slide_directive(slide.nodes);
+            let slide_warnings = slide_warnings
+                .into_iter()
+                .map(|x| format!("slide {} : {x}", index + 1));
+            warnings.extend(slide_warnings);
+            let (notes, notes_edits) = split_notes(&text, slide.nodes);
+            edits.extend(notes_edits);
+            // what the directive and the notes take out is left as is
+            let visible = slide
+                .nodes
+                .iter()
+                .filter(|node| {
+                    let start = node_range(node).start;
+                    !edits.iter().any(|(range, _)| range.contains(&start))
+                })
+                .collect::<Vec<_>>();
+            let (references, footnotes) = definitions.resolve(&text, &visible);
+            edits.extend(references);
+            let incremental = settings.incremental.unwrap_or(incremental);
+            let fragments = fragments_render(&text, &visible, incremental);
+            let fragments_count = fragments.len();
+            edits.extend(fragments);
+            edits.extend(toc_markers(&text, &visible));
+            let (heading_edits, headings, ids) = heading_markers(&visible);
+            edits.extend(heading_edits);
+            let source = apply_edits(&text, slide.range, edits);
+            // blank lines left at the end by removed nodes would make a last list loose
+            let source = format!("{}{footnotes}", source.trim_end());
+            let details = (section, sub, settings, fragments_count, headings, ids);
+            (source, (details, notes))
+        })
+        .unzip();
+    let mut slugger = Slugger::default();
+    for ((.., ids), _) in &details {
+        ids.iter().for_each(|x| slugger.register(x));
+    }
+    // the notes go through the cache too, they are compiled like the slides
+    let notes = details.iter().map(|(_, notes)| notes.as_str());
+    let mut compiled = cache.compile(sources.iter().map(String::as_str).chain(notes).collect());
+    let notes = compiled.split_off(sources.len());
+    let mut slides = compiled
+        .into_iter()
+        .zip(notes)
+        .zip(details)
+        .map(|((content, notes), (details, _))| {
+            let (section, sub, settings, fragments, headings, _) = details;
+            let (content, headings) = headings_render(&content, headings, &mut slugger);
+            Slide {
+                fragments,
+                section,
+                sub,
+                settings,
+                content,
+                notes,
+                headings,
+            }
+        })
+        .collect::<Vec<_>>();
+    let outline = document_outline(&slides);
+    for (index, slide) in slides.iter_mut().enumerate() {
+        slide.content = toc_render(&slide.content, &outline, index);
+    }
+    Ok(Document {
+        front_matter,
+        slides,
+        dependencies,
+        warnings,
+    })
+}
+
+/// compiled slides keyed by the hash of their markdown source,
+/// so a save only recompiles the slides that were edited.
+#[derive(Default)]
+pub struct SlidesCache(Mutex<HashMap<u64, String>>);
+
+impl SlidesCache {
+    pub fn compile(&self, sources: Vec<&str>) -> Vec<String> {
+        let mut cache = self.0.lock().unwrap();
+        let hashed = sources
+            .into_iter()
+            .map(|source| (source_hash(source), source))
+            .collect::<Vec<_>>();
+        let compiled = hashed
+            .par_iter()
+            .filter(|(hash, _)| !cache.contains_key(hash))
+            .map(|(hash, source)| (*hash, compile_slide(source)))
+            .collect::<HashMap<_, _>>();
+        let slides = hashed
+            .iter()
+            .map(|(hash, _)| {
+                compiled
+                    .get(hash)
+                    .or_else(|| cache.get(hash))
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        cache.retain(|hash, _| hashed.iter().any(|(x, _)| x == hash));
+        cache.extend(compiled);
+        slides
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn compile_slide(source: &str) -> String {
+    let root = markdown_parse(source);
+    let mut placeholders = Placeholders::new(source);
+    let mut edits = containers_render(source, &root);
+    edits.extend(code_syntax_highlight(&root, &mut placeholders));
+    edits.extend(math_render(source, &root, &mut placeholders));
+    let source = apply_edits(source, 0..source.len(), edits);
+    placeholders.restore(&markdown_compile(source))
 }
 
 pub fn markdown_compile(source: String) -> String {
@@ -121,16 +590,154 @@ pub fn markdown_compile(source: String) -> String {
         allow_dangerous_protocol: true,
         ..CompileOptions::default()
     };
-    let parse = ParseOptions::gfm();
+    let parse = parse_options();
     let options = Options { compile, parse };
-    markdown::to_html_with_options(&source, &options).unwrap()
+    markdown::to_html_with_options(&source, &options).unwrap_or_else(|err| {
+        format!(
+            r#"<div class="markdown-error">{}</div>"#,
+            escape_html(&err.to_string())
+        )
+    })
+}
+
+pub fn parse_options() -> ParseOptions {
+    ParseOptions {
+        constructs: Constructs {
+            frontmatter: true,
+            math_flow: true,
+            math_text: true,
+            ..Constructs::gfm()
+        },
+        ..ParseOptions::gfm()
+    }
+}
+
+/// the mdast of `text`, markdown without mdx constructs always parses
+pub fn markdown_parse(text: &str) -> Node {
+    to_mdast(text, &parse_options()).unwrap_or_else(|_| {
+        Node::Root(Root {
+            children: Vec::new(),
+            position: None,
+        })
+    })
+}
+
+/// the byte range of `node` in the source it was parsed from
+pub fn node_range(node: &Node) -> Range<usize> {
+    node.position()
+        .map(|x| x.start.offset..x.end.offset)
+        .unwrap_or_default()
+}
+
+/// a replacement of a byte range of a markdown source
+pub type Edit = (Range<usize>, String);
+
+/// the source of `range` in `text` with the `edits` made to it,
+/// an edit overlapping an earlier one is dropped
+pub fn apply_edits(text: &str, range: Range<usize>, mut edits: Vec<Edit>) -> String {
+    // an insertion goes before a replacement starting at the same place
+    edits.sort_by_key(|(x, _)| (x.start, x.end));
+    let mut result = String::with_capacity(range.len());
+    let mut begin = range.start;
+    for (edit, replacement) in edits {
+        if edit.start < begin {
+            continue;
+        }
+        result.push_str(&text[begin..edit.start]);
+        result.push_str(&replacement);
+        begin = edit.end;
+    }
+    result.push_str(&text[begin..range.end]);
+    result
+}
+
+/// the canonical path of `path` when it exists in the canonical directory `home`,
+/// so a `../` path or a symbolic link can not reach the files around it
+pub fn contained_path(home: &Path, path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().filter(|x| x.starts_with(home))
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
-pub fn emit_markdown(app: &AppHandle, index: usize, len: usize, slide: &String) {
-    let output = EmittedMarkdown::new(index + 1, len, slide);
+/// emits the slide at `index` with `fragment` fragments revealed to every window
+/// and its notes to the presenter one
+pub fn emit_markdown(app: &AppHandle, index: usize, fragment: usize, slides: &[Slide]) {
+    let slide = slides.get(index).cloned().unwrap_or_default();
+    let stack = Stack {
+        section: slide.section + 1,
+        sections: slides.last().map(|x| x.section + 1).unwrap_or_default(),
+        current: slide.sub + 1,
+        len: slides.iter().filter(|x| x.section == slide.section).count(),
+    };
+    let mut settings = slide.settings;
+    let port = app.state::<BackendContext>().port;
+    settings.background_image = settings.background_image.map(|x| asset_url(&x, port));
+    let output = EmittedMarkdown::new(
+        index + 1,
+        slides.len(),
+        fragment,
+        stack,
+        settings,
+        slide.content,
+    );
     app.emit(CONTENT_EVENT, output).unwrap();
+    let presenter = EmittedPresenter {
+        next: slides
+            .get(index + 1)
+            .map(|x| x.content.clone())
+            .unwrap_or_default(),
+        notes: slide.notes,
+    };
+    app.emit_to(PRESENTER_WINDOW, PRESENTER_EVENT, presenter)
+        .unwrap();
+}
+
+/// where the assets server serves a path relative to the slides
+fn asset_url(path: &str, port: u16) -> String {
+    if path.contains("://") {
+        path.to_string()
+    } else {
+        format!("http://localhost:{port}/{}", path.trim_start_matches('/'))
+    }
+}
+
+/// the headings of every slide, in order
+fn document_outline(slides: &[Slide]) -> Vec<OutlineHeading> {
+    slides
+        .iter()
+        .enumerate()
+        .flat_map(|(slide, x)| {
+            x.headings.iter().map(move |heading| OutlineHeading {
+                level: heading.level,
+                text: heading.text.clone(),
+                id: heading.id.clone(),
+                slide,
+            })
+        })
+        .collect()
+}
+
+pub fn emit_outline(app: &AppHandle, slides: &[Slide]) {
+    let headings = document_outline(slides);
+    app.emit(OUTLINE_EVENT, EmittedOutline { headings })
+        .unwrap();
 }
 
 pub fn emit_config(app: &AppHandle, config: EmittedConfig) {
     app.emit(CONFIG_EVENT, config).unwrap();
 }
+
+pub fn emit_front_matter(app: &AppHandle) {
+    let context = app.state::<BackendContext>();
+    let front_matter = context.front_matter.lock().unwrap().clone();
+    let output = EmittedFrontMatter {
+        front_matter,
+        highlight_css: document_highlight_css(app),
+    };
+    app.emit(FRONT_MATTER_EVENT, output).unwrap();
+}
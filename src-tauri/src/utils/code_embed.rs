@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+
+use markdown::mdast::Node;
+
+use super::{contained_path, escape_html, include::LineRange, Edit};
+
+/// the edits filling the code blocks whose info string holds `file=path` with that file,
+/// or with the `lines=a-b` or `region=name` part of it.
+///
+/// paths resolve against `home` and must stay in `root_dir`, the served directory,
+/// every embedded file is pushed to `dependencies`.
+pub fn embed_code(
+    text: &str,
+    root: &Node,
+    home: &Path,
+    root_dir: &Path,
+    dependencies: &mut Vec<PathBuf>,
+) -> Vec<Edit> {
+    let mut embeds = Vec::new();
+    collect_embeds(root, &mut embeds);
+
+    embeds
+        .into_iter()
+        .map(|(begin, end, embed)| {
+            let replacement = match embed.read(home, root_dir, dependencies) {
+                Ok(code) => fenced(text, begin, end, &code),
+                Err(err) => {
+                    format!(r#"<div class="markdown-error">{}</div>"#, escape_html(&err))
+                }
+            };
+            (begin..end, replacement)
+        })
+        .collect()
+}
+
+fn collect_embeds(node: &Node, embeds: &mut Vec<(usize, usize, Embed)>) {
+    if let Node::Code(code) = node {
+        let embed = code.meta.as_deref().and_then(Embed::parse);
+        if let (Some(embed), Some(position)) = (embed, &code.position) {
+            embeds.push((position.start.offset, position.end.offset, embed));
+        }
+        return;
+    }
+    for child in node.children().into_iter().flatten() {
+        collect_embeds(child, embeds);
+    }
+}
+
+/// the code block at `begin..end` of `text` with `code` as its content,
+/// continuation lines keep the blockquote markers and indent of the block.
+fn fenced(text: &str, begin: usize, end: usize, code: &str) -> String {
+    let source = &text[begin..end];
+    let first_line = source.lines().next().unwrap_or_default();
+    let info = first_line.trim_start().trim_start_matches(['`', '~']);
+    let line_begin = text[..begin].rfind('\n').map(|x| x + 1).unwrap_or(0);
+    let indent = first_line.len() - first_line.trim_start().len();
+    let prefix = text[line_begin..begin + indent]
+        .chars()
+        .map(|x| if x == '>' { x } else { ' ' })
+        .collect::<String>();
+
+    let longest_run = code
+        .lines()
+        .map(|line| line.trim_start().chars().take_while(|x| *x == '`').count())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let body = code
+        .lines()
+        .map(|line| format!("{prefix}{line}\n"))
+        .collect::<String>();
+    format!(
+        "{}{fence}{info}\n{body}{prefix}{fence}",
+        &first_line[..indent]
+    )
+}
+
+struct Embed {
+    path: String,
+    /// an invalid range is kept to be reported in place of the code
+    lines: Option<Result<LineRange, String>>,
+    region: Option<String>,
+}
+
+impl Embed {
+    fn parse(meta: &str) -> Option<Self> {
+        let mut embed = Embed {
+            path: String::new(),
+            lines: None,
+            region: None,
+        };
+        for token in meta.split_whitespace() {
+            match token.split_once('=') {
+                Some(("file", path)) => embed.path = path.trim_matches('"').to_string(),
+                Some(("lines", lines)) => embed.lines = Some(lines.parse()),
+                Some(("region", region)) => embed.region = Some(region.to_string()),
+                _ => (),
+            }
+        }
+        (!embed.path.is_empty()).then_some(embed)
+    }
+
+    fn read(
+        &self,
+        home: &Path,
+        root_dir: &Path,
+        dependencies: &mut Vec<PathBuf>,
+    ) -> Result<String, String> {
+        let path = contained_path(root_dir, &home.join(&self.path)).ok_or_else(|| {
+            format!(
+                "can not embed {} : not a file of the slides directory",
+                self.path
+            )
+        })?;
+        if !dependencies.contains(&path) {
+            dependencies.push(path.clone());
+        }
+        let code = std::fs::read_to_string(&path)
+            .map_err(|err| format!("can not embed {} : {err}", self.path))?;
+        let lines = self
+            .lines
+            .clone()
+            .transpose()
+            .map_err(|err| format!("can not embed {} : {err}", self.path))?;
+        let code = match (&self.region, lines) {
+            (Some(region), _) => region_lines(&code, region)
+                .ok_or_else(|| format!("can not find region {region} in {}", self.path))?,
+            (None, Some(lines)) => lines.select(&code),
+            (None, None) => return Ok(code),
+        };
+        Ok(dedent(&code))
+    }
+}
+
+/// the lines between the `#region name` and `#endregion` comments of `code`,
+/// mdbook style `ANCHOR: name` and `ANCHOR_END: name` comments work too.
+///
+/// the marker lines of other regions are left out of the snippet.
+fn region_lines(code: &str, region: &str) -> Option<String> {
+    let mut lines = code.lines();
+    lines.find(|line| region_start(line) == Some(region))?;
+    let mut depth = 0usize;
+    let mut snippet = Vec::new();
+    for line in lines {
+        if region_start(line).is_some() {
+            depth += 1;
+            continue;
+        }
+        if let Some(name) = region_end(line) {
+            if name == region || (name.is_empty() && depth == 0) {
+                return Some(snippet.join("\n"));
+            }
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+        snippet.push(line);
+    }
+    Some(snippet.join("\n"))
+}
+
+fn region_start(line: &str) -> Option<&str> {
+    let (_, name) = line
+        .split_once("#region ")
+        .or_else(|| line.split_once("ANCHOR: "))?;
+    Some(
+        name.trim()
+            .trim_end_matches("-->")
+            .trim_end_matches("*/")
+            .trim(),
+    )
+}
+
+fn region_end(line: &str) -> Option<&str> {
+    let (_, name) = line
+        .split_once("#endregion")
+        .or_else(|| line.split_once("ANCHOR_END:"))?;
+    Some(
+        name.trim()
+            .trim_end_matches("-->")
+            .trim_end_matches("*/")
+            .trim(),
+    )
+}
+
+fn dedent(code: &str) -> String {
+    let indent = code
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| x.len() - x.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    code.lines()
+        .map(|x| x.get(indent..).unwrap_or(x.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,4 +1,34 @@
-use syntect::{highlighting::ThemeSet, html::highlighted_html_for_string, parsing::SyntaxSet};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{LazyLock, RwLock},
+};
+
+use config::{HighlightCss, HighlightThemes};
+use markdown::mdast::Node;
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxDefinition, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use super::{diagram_render::diagram_render, placeholders::Placeholders, Edit};
+
+mod code_meta;
+use code_meta::CodeMeta;
+
+static SYNTAX_SET: LazyLock<RwLock<SyntaxSet>> =
+    LazyLock::new(|| RwLock::new(SyntaxSet::load_defaults_newlines()));
+static THEME_SET: LazyLock<RwLock<ThemeSet>> =
+    LazyLock::new(|| RwLock::new(ThemeSet::load_defaults()));
+static CODE_ALIASES: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+const SYNTAXES_DIR: &str = "syntaxes";
+const THEMES_DIR: &str = "themes";
+
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
 
 #[derive(Debug)]
 pub struct CodeBlock {
@@ -6,64 +36,216 @@ pub struct CodeBlock {
     end: usize,
     content: String,
     lang: String,
+    meta: CodeMeta,
 }
 
-pub fn code_syntax_highlight(source: &str) -> String {
-    let mut source = source.chars().collect::<Vec<_>>();
-    let codes = extract_code_blocks(&source);
-    let ps = SyntaxSet::load_defaults_newlines();
-    let ts = ThemeSet::load_defaults();
-    let theme = &ts.themes["base16-ocean.dark"];
-    for code in codes {
-        let syntax = ps
-            .find_syntax_by_extension(&code.lang)
-            .unwrap_or(ps.find_syntax_by_extension("sh").unwrap());
-        let html = highlighted_html_for_string(&code.content, &ps, syntax, theme).unwrap();
-        source.splice(code.begin..(code.end + 3), html.chars().collect::<Vec<_>>());
+/// the edits swapping every code block of the source parsed to `root`
+/// for a placeholder of its highlighted html
+pub fn code_syntax_highlight(root: &Node, placeholders: &mut Placeholders) -> Vec<Edit> {
+    let mut codes = Vec::new();
+    collect_code_blocks(root, &mut codes);
+    let ps = &*SYNTAX_SET.read().unwrap();
+    codes
+        .iter()
+        .map(|code| {
+            let html =
+                diagram_render(&code.lang, &code.content).unwrap_or_else(|| highlight(ps, code));
+            (code.begin..code.end, placeholders.push(html))
+        })
+        .collect()
+}
+
+fn highlight(ps: &SyntaxSet, code: &CodeBlock) -> String {
+    let syntax = find_syntax(ps, code);
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, ps, CLASS_STYLE);
+    for line in LinesWithEndings::from(&code.content) {
+        generator
+            .parse_html_for_line_which_includes_newline(line)
+            .unwrap();
     }
+    code.meta.render(&generator.finalize())
+}
 
-    String::from_iter(source)
+/// resolves the fence language through the aliases, then by token and syntax name,
+/// unlabeled blocks are detected from their first line, anything else is plain text.
+fn find_syntax<'a>(ps: &'a SyntaxSet, code: &CodeBlock) -> &'a SyntaxReference {
+    let lang = code.lang.to_lowercase();
+    let syntax = if lang.is_empty() {
+        code.content
+            .lines()
+            .next()
+            .and_then(|line| ps.find_syntax_by_first_line(line))
+    } else {
+        let aliases = CODE_ALIASES.read().unwrap();
+        let lang = aliases.get(&lang).unwrap_or(&lang);
+        ps.find_syntax_by_token(lang).or_else(|| {
+            ps.syntaxes()
+                .iter()
+                .find(|syntax| syntax.name.eq_ignore_ascii_case(lang))
+        })
+    };
+    syntax.unwrap_or_else(|| ps.find_syntax_plain_text())
 }
 
-pub fn extract_code_blocks(source: &[char]) -> Vec<CodeBlock> {
-    let mut all_blocks = Vec::<CodeBlock>::new();
-    let mut code_block = Vec::<char>::new();
-    let mut code_lang = Vec::<char>::new();
-    let mut code_began = None::<usize>;
-    let mut lang_done = false;
-    for (i, three_chars) in source.windows(3).enumerate() {
-        let three_chars_are_seperator = three_chars.iter().all(|x| *x == '`');
-        match code_began {
-            Some(begin) => {
-                if three_chars_are_seperator {
-                    let block = CodeBlock {
-                        begin,
-                        end: i,
-                        content: String::from_iter(code_block.iter()),
-                        lang: String::from_iter(code_lang.iter()).trim().to_string(),
-                    };
-                    all_blocks.push(block);
-                    code_block.clear();
-                    code_lang.clear();
-                    code_began = None;
-                    lang_done = false;
-                }
-                if i.checked_sub(begin).is_some_and(|i| i >= three_chars.len()) {
-                    let c = three_chars[0];
-                    if lang_done {
-                        code_block.push(c);
-                    } else {
-                        code_lang.push(c);
-                        lang_done = c == '\n';
-                    }
-                }
-            }
-            None => {
-                if three_chars_are_seperator {
-                    code_began = Some(i);
-                }
+/// replaces the language aliases, returns whether they changed
+pub fn set_code_aliases(aliases: &HashMap<String, String>) -> bool {
+    let aliases = aliases
+        .iter()
+        .map(|(alias, lang)| (alias.to_lowercase(), lang.to_lowercase()))
+        .collect::<HashMap<_, _>>();
+    let mut current = CODE_ALIASES.write().unwrap();
+    if *current == aliases {
+        return false;
+    }
+    *current = aliases;
+    true
+}
+
+/// the stylesheets for the classes `code_syntax_highlight` emits,
+/// the frontend picks one of them depending on the active theme.
+pub fn highlight_css(HighlightThemes { light, dark }: &HighlightThemes) -> HighlightCss {
+    let defaults = HighlightThemes::default();
+    HighlightCss {
+        light: theme_css(light, &defaults.light),
+        dark: theme_css(dark, &defaults.dark),
+    }
+}
+
+fn theme_css(name: &str, fallback: &str) -> String {
+    let theme_set = THEME_SET.read().unwrap();
+    theme_set
+        .themes
+        .get(name)
+        .or_else(|| theme_set.themes.get(fallback))
+        .and_then(|theme| css_for_theme_with_class_style(theme, CLASS_STYLE).ok())
+        .unwrap_or_default()
+}
+
+/// merges `syntaxes/*.sublime-syntax` and `themes/*.tmTheme` found in `config_dir`
+/// with the default sets, returns an error message for every file that failed to load.
+pub fn load_user_definitions(config_dir: &Path) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let mut syntaxes = SyntaxSet::load_defaults_newlines().into_builder();
+    for path in files_with_extension(&config_dir.join(SYNTAXES_DIR), "sublime-syntax") {
+        let definition = std::fs::read_to_string(&path)
+            .map_err(|x| x.to_string())
+            .and_then(|text| {
+                SyntaxDefinition::load_from_str(&text, true, None).map_err(|x| x.to_string())
+            });
+        match definition {
+            Ok(definition) => syntaxes.add(definition),
+            Err(err) => errors.push(format!("{} : {}", path.display(), err)),
+        }
+    }
+
+    let mut themes = ThemeSet::load_defaults();
+    for path in files_with_extension(&config_dir.join(THEMES_DIR), "tmTheme") {
+        match ThemeSet::get_theme(&path) {
+            Ok(theme) => {
+                let name = path.file_stem().unwrap().to_string_lossy().to_string();
+                themes.themes.insert(name, theme);
             }
+            Err(err) => errors.push(format!("{} : {}", path.display(), err)),
         }
     }
-    all_blocks
+
+    *SYNTAX_SET.write().unwrap() = syntaxes.build();
+    *THEME_SET.write().unwrap() = themes;
+    errors
+}
+
+/// the directories `load_user_definitions` reads from
+pub fn user_definition_dirs(config_dir: &Path) -> [PathBuf; 2] {
+    [config_dir.join(SYNTAXES_DIR), config_dir.join(THEMES_DIR)]
+}
+
+pub fn is_user_definition(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|x| x == "sublime-syntax" || x == "tmTheme")
+}
+
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut paths = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|x| x == extension))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn collect_code_blocks(node: &Node, all_blocks: &mut Vec<CodeBlock>) {
+    if let Node::Code(code) = node {
+        if let Some(position) = &code.position {
+            all_blocks.push(CodeBlock {
+                begin: position.start.offset,
+                end: position.end.offset,
+                content: format!("{}\n", code.value),
+                lang: code.lang.clone().unwrap_or_default(),
+                meta: CodeMeta::parse(code.meta.as_deref().unwrap_or_default()),
+            });
+        }
+        return;
+    }
+    for child in node.children().into_iter().flatten() {
+        collect_code_blocks(child, all_blocks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{apply_edits, markdown_compile, markdown_parse},
+        code_syntax_highlight, Placeholders,
+    };
+
+    /// compares the compiled `golden/{name}.md` with `golden/{name}.html`
+    macro_rules! golden {
+        ($name:ident) => {
+            #[test]
+            fn $name() {
+                let source = include_str!(concat!(
+                    "code_syntax_highlight/golden/",
+                    stringify!($name),
+                    ".md"
+                ));
+                let expected = include_str!(concat!(
+                    "code_syntax_highlight/golden/",
+                    stringify!($name),
+                    ".html"
+                ));
+                assert_eq!(compile(source), expected);
+            }
+        };
+    }
+
+    fn compile(source: &str) -> String {
+        let root = markdown_parse(source);
+        let mut placeholders = Placeholders::new(source);
+        let edits = code_syntax_highlight(&root, &mut placeholders);
+        let source = apply_edits(source, 0..source.len(), edits);
+        placeholders.restore(&markdown_compile(source))
+    }
+
+    golden!(nested_fences);
+    golden!(tildes);
+    golden!(longer_closing_fence);
+    golden!(list);
+    golden!(blockquote);
+    golden!(indented);
+    golden!(inline_code);
+    golden!(language_meta);
+
+    #[test]
+    fn placeholder_like_text_is_kept() {
+        let source = "<!--iquity0:0-->\n\n```text\ncode\n```\n";
+        let html = compile(source);
+        assert!(html.starts_with("<!--iquity0:0-->\n"));
+        assert!(html.contains(r#"<pre class="hl-code">"#));
+    }
 }
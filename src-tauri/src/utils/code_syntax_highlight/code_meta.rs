@@ -0,0 +1,120 @@
+use std::ops::RangeInclusive;
+
+/// options written after the language in a fence info string
+/// like ```` ```rust {3,5-7} linenos collapse=20 ````
+#[derive(Debug, Default)]
+pub struct CodeMeta {
+    line_numbers: bool,
+    highlighted_lines: Vec<RangeInclusive<usize>>,
+    collapse: Option<usize>,
+}
+
+impl CodeMeta {
+    pub fn parse(meta: &str) -> Self {
+        let mut result = Self::default();
+        let mut rest = meta.to_string();
+        if let (Some(begin), Some(end)) = (meta.find('{'), meta.find('}')) {
+            if begin < end {
+                result.highlighted_lines = parse_line_ranges(&meta[begin + 1..end]);
+                rest.replace_range(begin..=end, " ");
+            }
+        }
+        for token in rest.split_whitespace() {
+            match token.split_once('=') {
+                None if token == "linenos" => result.line_numbers = true,
+                Some(("collapse", limit)) => result.collapse = limit.parse().ok(),
+                _ => (),
+            }
+        }
+        result
+    }
+
+    fn is_plain(&self) -> bool {
+        !self.line_numbers && self.highlighted_lines.is_empty() && self.collapse.is_none()
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted_lines.iter().any(|x| x.contains(&line))
+    }
+
+    /// wraps the highlighted `html` of a code block in its `<pre>`,
+    /// adding the line gutter, emphasized lines and the expander when asked for.
+    pub fn render(&self, html: &str) -> String {
+        if self.is_plain() {
+            return format!(r#"<pre class="hl-code">{html}</pre>"#);
+        }
+        let lines = balanced_lines(html);
+        let body = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let number = i + 1;
+                let class = if self.is_highlighted(number) {
+                    "code-line code-line-highlight"
+                } else {
+                    "code-line"
+                };
+                let gutter = if self.line_numbers {
+                    format!(r#"<span class="code-line-number">{number}</span>"#)
+                } else {
+                    String::new()
+                };
+                format!(r#"<span class="{class}">{gutter}{line}</span>"#)
+            })
+            .collect::<String>();
+        let pre = format!(r#"<pre class="hl-code code-lines">{body}</pre>"#);
+        match self.collapse {
+            Some(limit) if lines.len() > limit => format!(
+                r#"<details class="code-collapse"><summary>{} lines</summary>{pre}</details>"#,
+                lines.len()
+            ),
+            _ => pre,
+        }
+    }
+}
+
+fn parse_line_ranges(ranges: &str) -> Vec<RangeInclusive<usize>> {
+    ranges
+        .split(',')
+        .filter_map(|range| {
+            let range = range.trim();
+            match range.split_once('-') {
+                Some((begin, end)) => Some(begin.trim().parse().ok()?..=end.trim().parse().ok()?),
+                None => range.parse().ok().map(|line| line..=line),
+            }
+        })
+        .collect()
+}
+
+/// splits syntect html into lines, closing the spans still open at the end
+/// of a line and reopening them on the next one so every line stands alone.
+fn balanced_lines(html: &str) -> Vec<String> {
+    let mut segments = html.split('\n').collect::<Vec<_>>();
+    if segments
+        .last()
+        .is_some_and(|x| x.split("</span>").all(str::is_empty))
+    {
+        segments.pop();
+    }
+    let mut open_spans = Vec::<&str>::new();
+    segments
+        .into_iter()
+        .map(|segment| {
+            let mut line = open_spans.concat();
+            line.push_str(segment);
+            let mut rest = segment;
+            while let Some(begin) = rest.find('<') {
+                rest = &rest[begin..];
+                let end = rest.find('>').map(|x| x + 1).unwrap_or(rest.len());
+                if rest.starts_with("</span>") {
+                    open_spans.pop();
+                } else if rest.starts_with("<span") {
+                    open_spans.push(&rest[..end]);
+                }
+                rest = &rest[end..];
+            }
+            line.push_str(&"</span>".repeat(open_spans.len()));
+            line
+        })
+        .collect()
+}
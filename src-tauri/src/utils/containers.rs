@@ -0,0 +1,87 @@
+use markdown::mdast::Node;
+
+use super::{escape_html, node_range, Edit};
+
+const CONTAINER_FENCE: &str = ":::";
+
+/// the edits turning `::: name` ... `:::` containers of `source`, parsed to `root`,
+/// into `<div class="name">` blocks, like `::: columns` holding `::: column` ones.
+///
+/// a fence is a line of a paragraph, so containers work in list items and
+/// blockquotes while code blocks are left untouched, its div tag stands alone
+/// between blank lines written with the prefix of the line, so the content
+/// of the container is still parsed as markdown at the same nesting.
+pub fn containers_render(source: &str, root: &Node) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut depth = 0usize;
+    collect_fences(source, root, &mut depth, &mut edits);
+    if depth > 0 {
+        let closing = "\n\n</div>".repeat(depth);
+        edits.push((source.len()..source.len(), closing));
+    }
+    edits
+}
+
+fn collect_fences(source: &str, node: &Node, depth: &mut usize, edits: &mut Vec<Edit>) {
+    if !matches!(node, Node::Paragraph(_)) {
+        for child in node.children().into_iter().flatten() {
+            collect_fences(source, child, depth, edits);
+        }
+        return;
+    }
+    let range = node_range(node);
+    let mut line_start = range.start;
+    for line in source[range.clone()].split('\n') {
+        // the first line starts with the paragraph, the others with their own markers
+        let content = if line_start == range.start {
+            line
+        } else {
+            line.trim_start_matches([' ', '\t', '>'])
+        };
+        let content_start = line_start + line.len() - content.len();
+        let prefix = block_prefix(source, content_start);
+        let tag = match container_line(content.trim_end()) {
+            Some(Some(classes)) => {
+                *depth += 1;
+                Some(format!(
+                    "<div class=\"{}\">",
+                    escape_html(&classes.join(" "))
+                ))
+            }
+            Some(None) if *depth > 0 => {
+                *depth -= 1;
+                Some("</div>".to_string())
+            }
+            _ => None,
+        };
+        if let Some(tag) = tag {
+            let line_end = content_start + content.len();
+            edits.push((
+                content_start..line_end,
+                format!("\n{prefix}{tag}\n{prefix}"),
+            ));
+        }
+        line_start += line.len() + 1;
+    }
+}
+
+/// the blockquote markers and indent written before `at` on its line,
+/// with the list item markers blanked out
+pub fn block_prefix(source: &str, at: usize) -> String {
+    let line_start = source[..at].rfind('\n').map_or(0, |x| x + 1);
+    source[line_start..at]
+        .chars()
+        .map(|x| if x == '>' { x } else { ' ' })
+        .collect()
+}
+
+/// `Some(classes)` for a line opening a container, `Some(None)` for a closing one
+fn container_line(line: &str) -> Option<Option<Vec<&str>>> {
+    let rest = line.strip_prefix(CONTAINER_FENCE)?.trim_start_matches(':');
+    let classes = rest
+        .split_whitespace()
+        .map(|x| x.trim_start_matches('.'))
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    Some((!classes.is_empty()).then_some(classes))
+}
@@ -0,0 +1,196 @@
+use std::{collections::HashMap, ops::Range};
+
+use markdown::mdast::{Node, ReferenceKind};
+
+use super::{apply_edits, escape_html, node_range, Edit};
+
+/// the link reference, footnote and abbreviation definitions of a whole document,
+/// the references of every slide resolve against them whatever slide they are written on.
+#[derive(Debug, Default)]
+pub struct Definitions {
+    /// the url and title of every link reference definition
+    links: HashMap<String, (String, Option<String>)>,
+    /// the resolved source of every footnote definition
+    footnotes: HashMap<String, String>,
+    abbreviations: HashMap<String, String>,
+}
+
+/// what resolving the references of a slide found in it
+#[derive(Default)]
+struct References {
+    edits: Vec<Edit>,
+    cited: Vec<String>,
+    defined: Vec<String>,
+}
+
+impl Definitions {
+    /// the definitions of the document `text` parsed to `root`
+    pub fn new(text: &str, root: &Node) -> Self {
+        let mut definitions = Definitions::default();
+        let mut footnotes = Vec::new();
+        definitions.collect(text, root, &mut footnotes);
+        for (identifier, node) in footnotes {
+            let mut references = References::default();
+            definitions.references(text, node, &mut references);
+            let range = node_range(node);
+            let source = apply_edits(text, range, references.edits);
+            definitions.footnotes.entry(identifier).or_insert(source);
+        }
+        definitions
+    }
+
+    /// the first definition of an identifier wins, like in commonmark
+    fn collect<'a>(&mut self, text: &str, node: &'a Node, footnotes: &mut Vec<(String, &'a Node)>) {
+        match node {
+            Node::Definition(x) => {
+                self.links
+                    .entry(x.identifier.clone())
+                    .or_insert_with(|| (x.url.clone(), x.title.clone()));
+            }
+            Node::FootnoteDefinition(x) => footnotes.push((x.identifier.clone(), node)),
+            Node::Paragraph(_) => {
+                for (abbreviation, title) in
+                    abbreviations(node_source(text, node)).into_iter().flatten()
+                {
+                    self.abbreviations.entry(abbreviation).or_insert(title);
+                }
+            }
+            _ => {
+                for child in node.children().into_iter().flatten() {
+                    self.collect(text, child, footnotes);
+                }
+            }
+        }
+    }
+
+    /// the edits turning the link and image references among the top level `nodes`
+    /// of a slide into inline ones, wrapping its abbreviations in `<abbr>` and
+    /// dropping their definitions, with the source of the footnotes the slide
+    /// cites but does not define itself, to append to it.
+    pub fn resolve(&self, text: &str, nodes: &[&Node]) -> (Vec<Edit>, String) {
+        let mut references = References::default();
+        for node in nodes {
+            self.references(text, node, &mut references);
+        }
+        let mut footnotes = String::new();
+        let mut appended = references.defined;
+        for identifier in references.cited {
+            if appended.contains(&identifier) {
+                continue;
+            }
+            if let Some(footnote) = self.footnotes.get(&identifier) {
+                footnotes.push_str("\n\n");
+                footnotes.push_str(footnote);
+            }
+            appended.push(identifier);
+        }
+        (references.edits, footnotes)
+    }
+
+    fn references(&self, text: &str, node: &Node, references: &mut References) {
+        let range = node_range(node);
+        match node {
+            Node::Paragraph(_) if abbreviations(&text[range.clone()]).is_some() => {
+                references.edits.push((range, String::new()));
+                return;
+            }
+            Node::Text(_) if !self.abbreviations.is_empty() => {
+                let replaced = abbreviate(&text[range.clone()], &self.abbreviations);
+                if replaced != text[range.clone()] {
+                    references.edits.push((range, replaced));
+                }
+                return;
+            }
+            Node::LinkReference(x) => {
+                if let Some(link) = self.links.get(&x.identifier) {
+                    let edit = reference_edit(text, range, &x.reference_kind, link);
+                    references.edits.push(edit);
+                }
+            }
+            Node::ImageReference(x) => {
+                if let Some(link) = self.links.get(&x.identifier) {
+                    let edit = reference_edit(text, range, &x.reference_kind, link);
+                    references.edits.push(edit);
+                }
+            }
+            Node::FootnoteReference(x) => references.cited.push(x.identifier.clone()),
+            Node::FootnoteDefinition(x) => references.defined.push(x.identifier.clone()),
+            _ => (),
+        }
+        for child in node.children().into_iter().flatten() {
+            self.references(text, child, references);
+        }
+    }
+}
+
+/// swaps the label of a `[text][label]` or `[text][]` reference for the inline
+/// destination of its definition, a `[text]` one gets the destination appended.
+fn reference_edit(
+    text: &str,
+    range: Range<usize>,
+    kind: &ReferenceKind,
+    (url, title): &(String, Option<String>),
+) -> (Range<usize>, String) {
+    let url = url
+        .replace('\\', "\\\\")
+        .replace('<', "\\<")
+        .replace('>', "\\>");
+    let destination = match title {
+        Some(title) => format!(
+            "(<{url}> \"{}\")",
+            title.replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        None => format!("(<{url}>)"),
+    };
+    let label = match kind {
+        ReferenceKind::Shortcut => range.end,
+        ReferenceKind::Collapsed => range.end - 2,
+        ReferenceKind::Full => range.start + text[range.clone()].rfind('[').unwrap_or(range.len()),
+    };
+    (label..range.end, destination)
+}
+
+/// the abbreviations of a paragraph made only of `*[ABBR]: title` lines
+fn abbreviations(paragraph: &str) -> Option<Vec<(String, String)>> {
+    paragraph
+        .lines()
+        .map(|line| {
+            let (abbreviation, title) = line.trim().strip_prefix("*[")?.split_once("]:")?;
+            let abbreviation = abbreviation.trim();
+            (!abbreviation.is_empty()).then(|| (abbreviation.to_string(), title.trim().to_string()))
+        })
+        .collect()
+}
+
+fn abbreviate(text: &str, titles: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    'outer: while !rest.is_empty() {
+        let after_word = result.chars().last().is_some_and(char::is_alphanumeric);
+        if !after_word {
+            for (abbreviation, title) in titles.iter() {
+                let Some(tail) = rest.strip_prefix(abbreviation.as_str()) else {
+                    continue;
+                };
+                if tail.chars().next().is_some_and(char::is_alphanumeric) {
+                    continue;
+                }
+                result.push_str(&format!(
+                    r#"<abbr title="{}">{}</abbr>"#,
+                    escape_html(title),
+                    abbreviation
+                ));
+                rest = tail;
+                continue 'outer;
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+fn node_source<'a>(text: &'a str, node: &Node) -> &'a str {
+    &text[node_range(node)]
+}
@@ -0,0 +1,47 @@
+use pikchr::{Pikchr, PikchrFlags};
+use svgbob::Settings;
+
+use super::escape_html;
+
+mod dot_render;
+use dot_render::dot_render;
+
+/// renders the code blocks written in a diagram language to inline svg,
+/// returns `None` for every other language so it gets highlighted instead.
+///
+/// mermaid is left to the frontend, which lays it out in the webview.
+pub fn diagram_render(lang: &str, source: &str) -> Option<String> {
+    let svg = match lang {
+        "mermaid" => {
+            return Some(format!(
+                r#"<pre class="mermaid">{}</pre>"#,
+                escape_html(source)
+            ))
+        }
+        "svgbob" | "bob" => Ok(svgbob_render(source)),
+        "pikchr" => pikchr_render(source),
+        "dot" | "graphviz" => dot_render(source),
+        _ => return None,
+    };
+    let html = match svg {
+        Ok(svg) => format!(r#"<div class="diagram">{svg}</div>"#),
+        Err(err) => format!(r#"<pre class="diagram-error">{}</pre>"#, escape_html(&err)),
+    };
+    Some(html)
+}
+
+fn svgbob_render(source: &str) -> String {
+    let settings = Settings {
+        stroke_color: "currentColor".to_string(),
+        fill_color: "currentColor".to_string(),
+        background: "transparent".to_string(),
+        include_backdrop: false,
+        ..Settings::default()
+    };
+    svgbob::to_svg_with_settings(source, &settings)
+}
+
+fn pikchr_render(source: &str) -> Result<String, String> {
+    let svg = Pikchr::render(source, None, PikchrFlags::default())?;
+    Ok(svg.to_string().replace("rgb(0,0,0)", "currentColor"))
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{LazyLock, Mutex},
+};
+
+use layout::{
+    backends::svg::SVGWriter,
+    gv::{DotParser, GraphBuilder, Lexer, Token},
+};
+
+use crate::utils::source_hash;
+
+/// rendered diagrams keyed by the hash of their dot source, so editing
+/// the text around a graph does not lay it out again.
+static DOT_CACHE: LazyLock<Mutex<HashMap<u64, Result<String, String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+const DOT_CACHE_LIMIT: usize = 256;
+
+/// lays out a graphviz diagram, or reuses the svg of the same source.
+///
+/// the layout engine panics on some graphs it can not place,
+/// which must not take the whole app down.
+pub fn dot_render(source: &str) -> Result<String, String> {
+    let hash = source_hash(source);
+    if let Some(svg) = DOT_CACHE.lock().unwrap().get(&hash) {
+        return svg.clone();
+    }
+    let svg = catch_unwind(AssertUnwindSafe(|| dot_layout(source, hash)))
+        .unwrap_or_else(|_| Err("the graph could not be laid out".to_string()));
+    let mut cache = DOT_CACHE.lock().unwrap();
+    if cache.len() >= DOT_CACHE_LIMIT {
+        cache.clear();
+    }
+    cache.insert(hash, svg.clone());
+    svg
+}
+
+fn dot_layout(source: &str, hash: u64) -> Result<String, String> {
+    lexer_error(source).map_err(|pos| error_at(source, "invalid token", pos))?;
+    let mut parser = DotParser::new(source);
+    let graph = parser
+        .process()
+        .map_err(|err| error_at(source, &err, parser_pos(&parser)))?;
+
+    let mut builder = GraphBuilder::new();
+    builder.visit_graph(&graph);
+    let mut visual_graph = builder.get();
+    if visual_graph.num_nodes() == 0 {
+        return Err("the graph has no nodes".to_string());
+    }
+    let mut writer = SVGWriter::new();
+    visual_graph.do_it(false, false, false, &mut writer);
+
+    let svg = writer.finalize();
+    let svg = svg.find("<svg").map(|x| &svg[x..]).unwrap_or(&svg);
+    let prefix = format!("dot{hash:x}-");
+    Ok(svg
+        .replace("#000000ff", "currentColor")
+        .replace("#ffffffff", "transparent")
+        .replace(r#"id=""#, &format!(r#"id="{prefix}"#))
+        .replace(r##"href="#"##, &format!(r##"href="#{prefix}"##))
+        .replace("url(#", &format!("url(#{prefix}")))
+}
+
+/// the char offset of the first token the lexer rejects, the parser
+/// reading past it panics so this has to succeed before `source` reaches it.
+fn lexer_error(source: &str) -> Result<(), usize> {
+    let mut lexer = Lexer::from_string(source);
+    loop {
+        match lexer.next_token() {
+            Token::EOF => return Ok(()),
+            Token::Error(pos) => return Err(pos.saturating_sub(1)),
+            _ => (),
+        }
+    }
+}
+
+/// the char offset of the token the parser stopped on.
+///
+/// its lexer is private, the debug output is the only place showing
+/// the position, which is one char past the last token read.
+fn parser_pos(parser: &DotParser) -> usize {
+    let debug = format!("{parser:?}");
+    debug
+        .split_once("pos: ")
+        .and_then(|(_, x)| x.split(|x: char| !x.is_ascii_digit()).next())
+        .and_then(|x| x.parse::<usize>().ok())
+        .map_or(0, |x| x.saturating_sub(1))
+}
+
+fn error_at(source: &str, err: &str, pos: usize) -> String {
+    let line = source.chars().take(pos).filter(|x| *x == '\n').count();
+    let text = source.lines().nth(line).unwrap_or_default();
+    format!("{err}\n{} | {text}", line + 1)
+}
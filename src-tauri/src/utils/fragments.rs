@@ -0,0 +1,66 @@
+use markdown::mdast::Node;
+
+use super::{containers::block_prefix, node_range, slides_split::comment_content, Edit};
+
+const FRAGMENT_DIRECTIVE: &str = ".fragment";
+const INLINE_MARKER: &str = r#"<span class="fragment-marker"></span>"#;
+const BLOCK_MARKER: &str = r#"<div class="fragment-marker"></div>"#;
+/// reveals the whole list item holding it, even a loose one whose text is in a paragraph
+const ITEM_MARKER: &str = r#"<span class="fragment-marker fragment-item"></span>"#;
+
+/// the edits replacing the `<!-- .fragment -->` comments among the top level
+/// `nodes` of a slide, parsed from `text`, with markers the frontend reveals
+/// one at a time, the element holding a marker, or the block right before it,
+/// is the fragment.
+///
+/// a marker standing on its own is an html block, which runs until a blank line,
+/// so a blank line written with the prefix of the comment line follows it.
+///
+/// with `incremental` every list item of the slide becomes a fragment,
+/// there are as many fragments as edits.
+pub fn fragments_render(text: &str, nodes: &[&Node], incremental: bool) -> Vec<Edit> {
+    let mut markers = Vec::new();
+    for node in nodes {
+        collect_markers(text, node, true, incremental, &mut markers);
+    }
+    markers
+}
+
+fn collect_markers(
+    text: &str,
+    node: &Node,
+    flow: bool,
+    incremental: bool,
+    markers: &mut Vec<Edit>,
+) {
+    match node {
+        Node::Html(html) if comment_content(&html.value) == Some(FRAGMENT_DIRECTIVE) => {
+            let range = node_range(node);
+            let marker = if flow {
+                format!("{BLOCK_MARKER}\n{}", block_prefix(text, range.start))
+            } else {
+                INLINE_MARKER.to_string()
+            };
+            markers.push((range, marker));
+            return;
+        }
+        Node::ListItem(item) if incremental => {
+            let first_end = item
+                .children
+                .first()
+                .filter(|x| matches!(x, Node::Paragraph(_)))
+                .map(|x| node_range(x).end);
+            if let Some(end) = first_end {
+                markers.push((end..end, ITEM_MARKER.to_string()));
+            }
+        }
+        _ => (),
+    }
+    let flow = matches!(
+        node,
+        Node::Root(_) | Node::Blockquote(_) | Node::ListItem(_) | Node::FootnoteDefinition(_)
+    );
+    for child in node.children().into_iter().flatten() {
+        collect_markers(text, child, flow, incremental, markers);
+    }
+}
@@ -0,0 +1,22 @@
+use config::FrontMatter;
+use markdown::mdast::Node;
+
+/// the settings of the yaml or toml front matter opening the document of `root`
+pub fn front_matter(root: &Node) -> Result<FrontMatter, String> {
+    match root.children().and_then(|x| x.first()) {
+        Some(Node::Yaml(yaml)) if yaml.value.trim().is_empty() => Ok(FrontMatter::default()),
+        Some(Node::Yaml(yaml)) => serde_yaml::from_str(&yaml.value).map_err(|x| x.to_string()),
+        Some(Node::Toml(toml)) => toml_front_matter(&toml.value),
+        _ => Ok(FrontMatter::default()),
+    }
+}
+
+/// toml has its own date type, the front matter keeps every date as text
+fn toml_front_matter(source: &str) -> Result<FrontMatter, String> {
+    let mut table = source.parse::<toml::Table>().map_err(|x| x.to_string())?;
+    if let Some(toml::Value::Datetime(date)) = table.get("date") {
+        let date = date.to_string();
+        table.insert("date".to_string(), toml::Value::String(date));
+    }
+    table.try_into().map_err(|x: toml::de::Error| x.to_string())
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use markdown::mdast::Node;
+
+use super::{node_range, Edit};
+
+/// a heading of a compiled slide and the id of its anchor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+}
+
+/// github compatible heading slugs, a repeated one gets a `-1`, `-2` ... suffix
+#[derive(Default)]
+pub struct Slugger(HashMap<String, usize>);
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let original = text
+            .to_lowercase()
+            .chars()
+            .filter_map(|x| match x {
+                ' ' => Some('-'),
+                x if x.is_alphanumeric() || x == '-' || x == '_' => Some(x),
+                _ => None,
+            })
+            .collect::<String>();
+        let mut slug = original.clone();
+        while self.0.contains_key(&slug) {
+            let count = self.0.entry(original.clone()).or_default();
+            *count += 1;
+            slug = format!("{original}-{count}");
+        }
+        self.0.insert(slug.clone(), 0);
+        slug
+    }
+
+    /// keeps the slugs clear of an id written in the document
+    pub fn register(&mut self, id: &str) {
+        self.0.entry(id.to_string()).or_default();
+    }
+}
+
+/// the edits putting a marker at the start of the content of every heading among
+/// the top level `nodes` of a slide, the level and text of each of them,
+/// and the ids the raw html of the slide sets.
+///
+/// the ids depend on the headings of the whole document while a slide
+/// compiles on its own, `headings_render` swaps the markers for them afterwards.
+pub fn heading_markers(nodes: &[&Node]) -> (Vec<Edit>, Vec<(u8, String)>, Vec<String>) {
+    let mut edits = Vec::new();
+    let mut headings = Vec::new();
+    let mut ids = Vec::new();
+    for node in nodes {
+        collect_headings(node, &mut edits, &mut headings, &mut ids);
+    }
+    (edits, headings, ids)
+}
+
+fn collect_headings(
+    node: &Node,
+    edits: &mut Vec<Edit>,
+    headings: &mut Vec<(u8, String)>,
+    ids: &mut Vec<String>,
+) {
+    match node {
+        Node::Heading(heading) => {
+            if let Some(first) = heading.children.first() {
+                let start = node_range(first).start;
+                edits.push((start..start, heading_marker(headings.len())));
+                headings.push((heading.depth, node.to_string()));
+            }
+        }
+        Node::Html(html) => ids.extend(html_ids(&html.value).map(String::from)),
+        _ => (),
+    }
+    for child in node.children().into_iter().flatten() {
+        collect_headings(child, edits, headings, ids);
+    }
+}
+
+fn heading_marker(index: usize) -> String {
+    format!(r#"<span data-heading="{index}"></span>"#)
+}
+
+/// gives the heading of every marker of a compiled slide the id of its slug
+pub fn headings_render(
+    html: &str,
+    headings: Vec<(u8, String)>,
+    slugger: &mut Slugger,
+) -> (String, Vec<Heading>) {
+    let mut html = html.to_string();
+    let mut rendered = Vec::new();
+    for (index, (level, text)) in headings.into_iter().enumerate() {
+        let marked = format!("<h{level}>{}", heading_marker(index));
+        if !html.contains(&marked) {
+            continue;
+        }
+        let id = slugger.slug(&text);
+        html = html.replacen(&marked, &format!("<h{level} id=\"{id}\">"), 1);
+        rendered.push(Heading { level, text, id });
+    }
+    (html, rendered)
+}
+
+/// the values of the `id` attributes of an html fragment
+fn html_ids(html: &str) -> impl Iterator<Item = &str> {
+    html.split(" id=\"")
+        .skip(1)
+        .filter_map(|x| x.split_once('"').map(|(id, _)| id))
+}
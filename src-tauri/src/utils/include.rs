@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use markdown::mdast::Node;
+
+use super::{
+    apply_edits, code_embed::embed_code, contained_path, escape_html, markdown_parse, node_range,
+    slides_split::comment_content, Edit,
+};
+
+const INCLUDE_DIRECTIVE: &str = "include:";
+
+/// replaces every top level `<!-- include: path lines=a-b -->` comment of `text`,
+/// read from `path` and parsed to `root`, with the content of the included file,
+/// and fills the code blocks embedding a file, `None` when nothing changed.
+///
+/// the relative urls of `text` and of the included files are rewritten to
+/// resolve from `root_dir`, the served directory, and every included or embedded
+/// file is pushed to `dependencies` so it can be watched.
+pub fn expand_includes(
+    text: &str,
+    root: &Node,
+    path: &Path,
+    root_dir: &Path,
+    dependencies: &mut Vec<PathBuf>,
+) -> Option<String> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let root_dir = root_dir
+        .canonicalize()
+        .unwrap_or_else(|_| root_dir.to_path_buf());
+    let mut stack = vec![path.clone()];
+    let edits = expand(text, root, &path, &root_dir, true, &mut stack, dependencies);
+    (!edits.is_empty()).then(|| apply_edits(text, 0..text.len(), edits))
+}
+
+/// the edits relocating the urls of a file, filling its embeds and its includes
+fn expand(
+    text: &str,
+    root: &Node,
+    path: &Path,
+    root_dir: &Path,
+    keep_front_matter: bool,
+    stack: &mut Vec<PathBuf>,
+    dependencies: &mut Vec<PathBuf>,
+) -> Vec<Edit> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let prefix = dir
+        .strip_prefix(root_dir)
+        .map(|x| x.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    let mut edits = Vec::new();
+    collect_urls(text, root, &prefix, keep_front_matter, &mut edits);
+    edits.extend(embed_code(text, root, dir, root_dir, dependencies));
+    for node in root.children().into_iter().flatten() {
+        let Node::Html(html) = node else {
+            continue;
+        };
+        let Some(include) = comment_content(&html.value).and_then(Include::parse) else {
+            continue;
+        };
+        let content =
+            include_file(&include, dir, root_dir, stack, dependencies).unwrap_or_else(|err| {
+                format!(r#"<div class="markdown-error">{}</div>"#, escape_html(&err))
+            });
+        // blank lines keep the included blocks apart from the ones around the comment
+        edits.push((node_range(node), format!("\n\n{content}\n\n")));
+    }
+    edits
+}
+
+fn include_file(
+    include: &Include,
+    dir: &Path,
+    root_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    dependencies: &mut Vec<PathBuf>,
+) -> Result<String, String> {
+    let lines = include
+        .lines
+        .clone()
+        .transpose()
+        .map_err(|err| format!("can not include {} : {err}", include.path))?;
+    let path = contained_path(root_dir, &dir.join(&include.path)).ok_or_else(|| {
+        format!(
+            "can not include {} : not a file of the slides directory",
+            include.path
+        )
+    })?;
+    if stack.contains(&path) {
+        let cycle = stack
+            .iter()
+            .chain([&path])
+            .map(|x| x.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(format!("include cycle : {cycle}"));
+    }
+    if !dependencies.contains(&path) {
+        dependencies.push(path.clone());
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .map_err(|err| format!("can not include {} : {err}", include.path))?;
+    let text = match lines {
+        Some(lines) => lines.select(&text),
+        None => text,
+    };
+
+    let root = markdown_parse(&text);
+    stack.push(path.clone());
+    let edits = expand(&text, &root, &path, root_dir, false, stack, dependencies);
+    stack.pop();
+    Ok(apply_edits(&text, 0..text.len(), edits))
+}
+
+/// rewrites the relative image, link and link definition urls of a file
+/// so they resolve from the served directory, `prefix` being the path of
+/// the file directory in it, its front matter is dropped unless `keep_front_matter`.
+fn collect_urls(
+    text: &str,
+    node: &Node,
+    prefix: &str,
+    keep_front_matter: bool,
+    edits: &mut Vec<Edit>,
+) {
+    let range = node_range(node);
+    let url = match node {
+        Node::Yaml(_) | Node::Toml(_) if !keep_front_matter => {
+            edits.push((range, String::new()));
+            return;
+        }
+        Node::Image(image) => Some(&image.url),
+        Node::Link(link) => Some(&link.url),
+        Node::Definition(definition) => Some(&definition.url),
+        _ => None,
+    };
+    if let Some(url) = url.filter(|x| !prefix.is_empty() && is_relative(x)) {
+        // the url follows the label, which may hold the same text
+        let source = &text[range.clone()];
+        let label_end = source.rfind("](").or_else(|| source.find("]:"));
+        if let Some(at) = label_end.and_then(|x| source[x..].find(url.as_str()).map(|y| x + y)) {
+            let at = range.start + at;
+            edits.push((at..at + url.len(), format!("{prefix}/{url}")));
+        }
+    }
+    for child in node.children().into_iter().flatten() {
+        collect_urls(text, child, prefix, keep_front_matter, edits);
+    }
+}
+
+fn is_relative(url: &str) -> bool {
+    !(url.is_empty() || url.contains(':') || url.starts_with('/') || url.starts_with('#'))
+}
+
+struct Include {
+    path: String,
+    /// an invalid range is kept to be reported where the file would go
+    lines: Option<Result<LineRange, String>>,
+}
+
+impl Include {
+    fn parse(comment: &str) -> Option<Self> {
+        let directive = comment.strip_prefix(INCLUDE_DIRECTIVE)?.trim();
+        let (path, lines) = match directive.rsplit_once(char::is_whitespace) {
+            Some((path, lines)) if lines.starts_with("lines=") => {
+                (path.trim(), Some(lines["lines=".len()..].parse()))
+            }
+            _ => (directive, None),
+        };
+        (!path.is_empty()).then(|| Include {
+            path: path.to_string(),
+            lines,
+        })
+    }
+}
+
+/// one based inclusive line range written `10-40`, `10-`, `-40` or `10`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl LineRange {
+    pub fn select(&self, text: &str) -> String {
+        let lines = text.lines().skip(self.start.saturating_sub(1));
+        let lines = match self.end {
+            Some(end) => lines
+                .take(end.saturating_add(1).saturating_sub(self.start))
+                .collect::<Vec<_>>(),
+            None => lines.collect(),
+        };
+        lines.join("\n")
+    }
+}
+
+impl std::str::FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = |x: &str| match x.trim().parse::<usize>() {
+            Ok(0) => Err("lines are numbered from 1".to_string()),
+            Ok(x) => Ok(x),
+            Err(err) => Err(format!("invalid line {x} : {err}")),
+        };
+        match s.split_once('-') {
+            Some((start, end)) => {
+                let start = match start.trim() {
+                    "" => 1,
+                    x => line(x)?,
+                };
+                let end = match end.trim() {
+                    "" => None,
+                    x => Some(line(x)?),
+                };
+                if end.is_some_and(|end| end < start) {
+                    return Err(format!("the line range {s} ends before it starts"));
+                }
+                Ok(LineRange { start, end })
+            }
+            None => {
+                let line = line(s)?;
+                Ok(LineRange {
+                    start: line,
+                    end: Some(line),
+                })
+            }
+        }
+    }
+}
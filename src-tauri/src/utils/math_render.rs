@@ -0,0 +1,84 @@
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use markdown::mdast::Node;
+
+use super::{escape_html, placeholders::Placeholders, Edit};
+
+#[derive(Debug)]
+pub struct MathBlock {
+    begin: usize,
+    end: usize,
+    latex: String,
+    display: bool,
+}
+
+/// the edits swapping every math node of `source`, parsed to `root`, for a placeholder
+/// of its rendered MathML, a `$` pair that reads as currency rather than math is escaped instead.
+pub fn math_render(source: &str, root: &Node, placeholders: &mut Placeholders) -> Vec<Edit> {
+    let mut maths = Vec::new();
+    collect_math_blocks(root, &mut maths);
+    maths
+        .iter()
+        .map(|math| {
+            let replacement = if is_currency(source, math) {
+                format!("\\{}\\$", &source[math.begin..math.end - 1])
+            } else if math.display {
+                placeholders.push(math.to_mathml())
+            } else {
+                placeholders.push_inline(math.to_mathml())
+            };
+            (math.begin..math.end, replacement)
+        })
+        .collect()
+}
+
+/// like pandoc, single dollar inline math can't start or end with a space
+/// and its closing dollar can't be followed by a digit, so `$5 and $10` stays text.
+fn is_currency(source: &str, math: &MathBlock) -> bool {
+    let delimited = &source[math.begin..math.end];
+    if math.display || delimited.starts_with("$$") {
+        return false;
+    }
+    let inner = delimited.trim_matches('$');
+    inner.starts_with(char::is_whitespace)
+        || inner.ends_with(char::is_whitespace)
+        || source[math.end..].starts_with(|x: char| x.is_ascii_digit())
+}
+
+impl MathBlock {
+    fn to_mathml(&self) -> String {
+        let style = if self.display {
+            DisplayStyle::Block
+        } else {
+            DisplayStyle::Inline
+        };
+        match latex_to_mathml(&self.latex, style) {
+            Ok(mathml) => mathml,
+            Err(err) => format!(
+                r#"<span class="math-error"><code>{}</code> {}</span>"#,
+                escape_html(&self.latex),
+                escape_html(&err.to_string()),
+            ),
+        }
+    }
+}
+
+fn collect_math_blocks(node: &Node, all_blocks: &mut Vec<MathBlock>) {
+    let (latex, position, display) = match node {
+        Node::Math(math) => (&math.value, &math.position, true),
+        Node::InlineMath(math) => (&math.value, &math.position, false),
+        _ => {
+            for child in node.children().into_iter().flatten() {
+                collect_math_blocks(child, all_blocks);
+            }
+            return;
+        }
+    };
+    if let Some(position) = position {
+        all_blocks.push(MathBlock {
+            begin: position.start.offset,
+            end: position.end.offset,
+            latex: latex.clone(),
+            display,
+        });
+    }
+}
@@ -0,0 +1,75 @@
+/// markup standing in for generated html while a slide compiles,
+/// the markdown compiler copies it through untouched so the generated html
+/// is spliced into its output afterwards instead of being parsed as markdown.
+///
+/// a block is replaced by an html comment, which starts an html block of its own,
+/// an inline node by an empty element, which stays in its paragraph.
+#[derive(Debug)]
+pub struct Placeholders {
+    name: String,
+    html: Vec<String>,
+}
+
+impl Placeholders {
+    /// picks a name `source` doesn't contain, so no user text can be mistaken for a placeholder
+    pub fn new(source: &str) -> Self {
+        let name = (0..)
+            .map(|n| format!("iquity{n}"))
+            .find(|name| {
+                !source.contains(&format!("{name}:")) && !source.contains(&format!("{name}>"))
+            })
+            .unwrap();
+        Placeholders {
+            name,
+            html: Vec::new(),
+        }
+    }
+
+    /// stores the `html` of a block and returns the comment to put in the source in its place
+    pub fn push(&mut self, html: String) -> String {
+        self.html.push(html);
+        format!("<!--{}:{}-->", self.name, self.html.len() - 1)
+    }
+
+    /// stores the `html` of an inline node and returns the element to put in its place
+    pub fn push_inline(&mut self, html: String) -> String {
+        self.html.push(html);
+        let name = &self.name;
+        format!("<{name}>{}</{name}>", self.html.len() - 1)
+    }
+
+    /// replaces every placeholder of the compiled `html` with what it stands for
+    pub fn restore(&self, html: &str) -> String {
+        if self.html.is_empty() {
+            return html.to_string();
+        }
+        let block = (format!("<!--{}:", self.name), "-->".to_string());
+        let inline = (format!("<{}>", self.name), format!("</{}>", self.name));
+        let mut result = String::with_capacity(html.len());
+        let mut rest = html;
+        loop {
+            let found = [&block, &inline]
+                .into_iter()
+                .filter_map(|(open, close)| Some((rest.find(open.as_str())?, open, close)))
+                .min_by_key(|(begin, ..)| *begin);
+            let Some((begin, open, close)) = found else {
+                break;
+            };
+            result.push_str(&rest[..begin]);
+            rest = &rest[begin + open.len()..];
+            let generated = rest.split_once(close.as_str()).and_then(|(index, after)| {
+                let html = self.html.get(index.parse::<usize>().ok()?)?;
+                Some((html, after))
+            });
+            match generated {
+                Some((html, after)) => {
+                    result.push_str(html);
+                    rest = after;
+                }
+                None => result.push_str(open),
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
@@ -0,0 +1,116 @@
+use config::SlideSettings;
+use markdown::mdast::Node;
+
+use super::{node_range, slides_split::comment_content, Edit};
+
+const SLIDE_DIRECTIVE: &str = "slide:";
+
+/// the layouts the frontend stylesheet knows
+const LAYOUTS: &[&str] = &[
+    "title",
+    "section",
+    "two-column",
+    "image-left",
+    "image-right",
+    "quote",
+    "full-image",
+];
+
+const COLOR_FUNCTIONS: &[&str] = &[
+    "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch", "color",
+];
+
+const NAMED_COLORS: &str =
+    "aliceblue antiquewhite aqua aquamarine azure beige bisque black blanchedalmond blue \
+    blueviolet brown burlywood cadetblue chartreuse chocolate coral cornflowerblue cornsilk \
+    crimson cyan darkblue darkcyan darkgoldenrod darkgray darkgreen darkgrey darkkhaki \
+    darkmagenta darkolivegreen darkorange darkorchid darkred darksalmon darkseagreen \
+    darkslateblue darkslategray darkslategrey darkturquoise darkviolet deeppink deepskyblue \
+    dimgray dimgrey dodgerblue firebrick floralwhite forestgreen fuchsia gainsboro \
+    ghostwhite gold goldenrod gray green greenyellow grey honeydew hotpink indianred indigo \
+    ivory khaki lavender lavenderblush lawngreen lemonchiffon lightblue lightcoral \
+    lightcyan lightgoldenrodyellow lightgray lightgreen lightgrey lightpink lightsalmon \
+    lightseagreen lightskyblue lightslategray lightslategrey lightsteelblue lightyellow \
+    lime limegreen linen magenta maroon mediumaquamarine mediumblue mediumorchid \
+    mediumpurple mediumseagreen mediumslateblue mediumspringgreen mediumturquoise \
+    mediumvioletred midnightblue mintcream mistyrose moccasin navajowhite navy oldlace \
+    olive olivedrab orange orangered orchid palegoldenrod palegreen paleturquoise \
+    palevioletred papayawhip peachpuff peru pink plum powderblue purple rebeccapurple red \
+    rosybrown royalblue saddlebrown salmon sandybrown seagreen seashell sienna silver \
+    skyblue slateblue slategray slategrey snow springgreen steelblue tan teal thistle \
+    tomato turquoise violet wheat white whitesmoke yellow yellowgreen transparent \
+    currentcolor";
+
+/// the settings of the `<!-- slide: background=img/bg.jpg class=centered theme=night layout=quote incremental=false -->`
+/// comment among the top level `nodes` of a slide, the edit taking it out
+/// and a warning for every value the directive ignores.
+pub fn slide_directive(nodes: &[Node]) -> (SlideSettings, Vec<Edit>, Vec<String>) {
+    let directive = nodes.iter().find_map(|node| {
+        let Node::Html(html) = node else {
+            return None;
+        };
+        let settings = comment_content(&html.value)?.strip_prefix(SLIDE_DIRECTIVE)?;
+        Some((node_range(node), settings))
+    });
+    let Some((range, directive)) = directive else {
+        return (SlideSettings::default(), Vec::new(), Vec::new());
+    };
+
+    let mut settings = SlideSettings::default();
+    let mut warnings = Vec::new();
+    for (key, value) in directive_pairs(directive) {
+        match key.as_str() {
+            "background" if is_color(&value) => settings.background_color = Some(value),
+            "background" => settings.background_image = Some(value),
+            "class" => settings.classes.extend(
+                value
+                    .split([' ', ','])
+                    .filter(|x| !x.is_empty())
+                    .map(String::from),
+            ),
+            "theme" => settings.theme = Some(value),
+            "layout" if LAYOUTS.contains(&value.as_str()) => settings.layout = Some(value),
+            "layout" => warnings.push(format!(
+                "unknown layout {value}, expected one of {}",
+                LAYOUTS.join(", ")
+            )),
+            "incremental" => settings.incremental = value.parse().ok(),
+            _ => (),
+        }
+    }
+    (settings, vec![(range, String::new())], warnings)
+}
+
+/// the `key=value` pairs of a directive, values may be double quoted
+fn directive_pairs(directive: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = directive.trim();
+    while let Some((key, tail)) = rest.split_once('=') {
+        let key = key.trim().to_string();
+        let (value, tail) = match tail.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => tail.split_once(char::is_whitespace).unwrap_or((tail, "")),
+        };
+        pairs.push((key, value.to_string()));
+        rest = tail.trim_start();
+    }
+    pairs
+}
+
+/// a css color: `#` and 3, 4, 6 or 8 hex digits, a functional notation
+/// like `rgb(0 0 0 / 50%)` or a named color, anything else is an image url
+fn is_color(value: &str) -> bool {
+    if let Some(hex) = value.strip_prefix('#') {
+        return [3, 4, 6, 8].contains(&hex.len()) && hex.chars().all(|x| x.is_ascii_hexdigit());
+    }
+    if let Some((function, arguments)) = value.split_once('(') {
+        return COLOR_FUNCTIONS.contains(&function.to_ascii_lowercase().as_str())
+            && arguments.strip_suffix(')').is_some_and(|x| {
+                x.chars()
+                    .all(|x| x.is_ascii_alphanumeric() || " ,.%/+-".contains(x))
+            });
+    }
+    NAMED_COLORS
+        .split_whitespace()
+        .any(|x| x.eq_ignore_ascii_case(value))
+}
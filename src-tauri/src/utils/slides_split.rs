@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use config::SlideSplit;
+use markdown::mdast::Node;
+
+use super::node_range;
+
+/// the source range of a slide in the document and its top level nodes
+#[derive(Debug, Clone)]
+pub struct SlideNodes<'a> {
+    pub range: Range<usize>,
+    pub nodes: &'a [Node],
+}
+
+/// cuts the document `text` into slides on its top level `nodes`
+pub fn slides_split<'a>(text: &str, nodes: &'a [Node], split: &SlideSplit) -> Vec<SlideNodes<'a>> {
+    split_nodes(text, 0..text.len(), nodes, split)
+}
+
+/// cuts a slide into the vertical stack of its sub slides
+pub fn sub_slides_split<'a>(
+    text: &str,
+    slide: SlideNodes<'a>,
+    split: &SlideSplit,
+) -> Vec<SlideNodes<'a>> {
+    if *split == SlideSplit::None {
+        return vec![slide];
+    }
+    split_nodes(text, slide.range, slide.nodes, split)
+}
+
+fn split_nodes<'a>(
+    text: &str,
+    range: Range<usize>,
+    nodes: &'a [Node],
+    split: &SlideSplit,
+) -> Vec<SlideNodes<'a>> {
+    let mut slides = Vec::new();
+    let mut begin = range.start;
+    let mut first = 0;
+    let mut has_content = false;
+    for (i, node) in nodes.iter().enumerate() {
+        let Range { start, end } = node_range(node);
+        // whether the node opens the next slide or is cut out of the document
+        let opens_slide = match (&split, node) {
+            (SlideSplit::ThematicBreak, Node::ThematicBreak(_)) => Some(false),
+            (SlideSplit::Heading(level), Node::Heading(heading)) if heading.depth <= *level => {
+                Some(true)
+            }
+            (SlideSplit::Marker(marker), Node::Html(html)) if is_comment(&html.value, marker) => {
+                Some(false)
+            }
+            (SlideSplit::Line(line), Node::Paragraph(_)) if text[start..end].trim() == line => {
+                Some(false)
+            }
+            _ => None,
+        };
+        let Some(opens_slide) = opens_slide else {
+            has_content |= is_content(node);
+            continue;
+        };
+        // front matter and comments alone do not make a slide
+        if has_content {
+            slides.push(SlideNodes {
+                range: begin..start,
+                nodes: &nodes[first..i],
+            });
+            begin = start;
+            first = i;
+        }
+        if !opens_slide {
+            begin = end;
+            first = i + 1;
+        }
+        has_content = opens_slide;
+    }
+    slides.push(SlideNodes {
+        range: begin..range.end,
+        nodes: &nodes[first..],
+    });
+
+    let slides = slides
+        .into_iter()
+        .filter(|x| !text[x.range.clone()].trim().is_empty())
+        .collect::<Vec<_>>();
+    if slides.is_empty() {
+        vec![SlideNodes {
+            range: range.end..range.end,
+            nodes: &[],
+        }]
+    } else {
+        slides
+    }
+}
+
+fn is_content(node: &Node) -> bool {
+    match node {
+        Node::Yaml(_) | Node::Toml(_) => false,
+        Node::Html(html) => comment_content(&html.value).is_none(),
+        _ => true,
+    }
+}
+
+fn is_comment(html: &str, content: &str) -> bool {
+    comment_content(html).is_some_and(|x| x == content)
+}
+
+pub fn comment_content(html: &str) -> Option<&str> {
+    html.trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")
+        .map(str::trim)
+}
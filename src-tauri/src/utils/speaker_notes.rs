@@ -0,0 +1,54 @@
+use markdown::mdast::Node;
+
+use super::{node_range, slides_split::comment_content, Edit};
+
+const NOTES_DIRECTIVE: &str = "notes:";
+const NOTES_TRAILERS: &[&str] = &["Notes:", "Note:"];
+
+/// the speaker notes among the top level `nodes` of a slide, written in
+/// `<!-- notes: ... -->` comments or after a top level `Note:` paragraph,
+/// and the edits taking them out of what the audience sees.
+///
+/// a `Note:` paragraph takes the blocks after it up to the next comment,
+/// like a directive, or the end of the slide.
+pub fn split_notes(text: &str, nodes: &[Node]) -> (String, Vec<Edit>) {
+    let mut notes = Vec::new();
+    let mut edits = Vec::new();
+    let mut rest = nodes;
+    while let Some((node, after)) = rest.split_first() {
+        rest = after;
+        let range = node_range(node);
+        match node {
+            Node::Html(html) => {
+                let Some(note) =
+                    comment_content(&html.value).and_then(|x| x.strip_prefix(NOTES_DIRECTIVE))
+                else {
+                    continue;
+                };
+                notes.push(note.trim().to_string());
+                edits.push((range, String::new()));
+            }
+            Node::Paragraph(_) => {
+                let Some(trailer) = NOTES_TRAILERS
+                    .iter()
+                    .find(|x| text[range.start..].starts_with(*x))
+                else {
+                    continue;
+                };
+                let taken = rest.iter().take_while(|x| !is_comment(x)).count();
+                let end = rest[..taken]
+                    .last()
+                    .map_or(range.end, |x| node_range(x).end);
+                rest = &rest[taken..];
+                notes.push(text[range.start + trailer.len()..end].trim().to_string());
+                edits.push((range.start..end, String::new()));
+            }
+            _ => (),
+        }
+    }
+    (notes.join("\n\n"), edits)
+}
+
+fn is_comment(node: &Node) -> bool {
+    matches!(node, Node::Html(html) if comment_content(&html.value).is_some())
+}
@@ -0,0 +1,52 @@
+use config::OutlineHeading;
+use markdown::mdast::Node;
+
+use super::{escape_html, node_range, slides_split::comment_content, Edit};
+
+const TOC_COMMENT: &str = "toc";
+const TOC_LINE: &str = "[[toc]]";
+const TOC_MARKER: &str = r#"<div class="toc-marker"></div>"#;
+/// the deepest heading level an agenda lists
+const TOC_DEPTH: u8 = 3;
+
+/// the edits replacing the `[[toc]]` and `<!-- toc -->` markers among the top level
+/// `nodes` of a slide with a placeholder `toc_render` fills once every heading is known,
+/// the placeholder is an html block so a blank line ends it.
+pub fn toc_markers(text: &str, nodes: &[&Node]) -> Vec<Edit> {
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let range = node_range(node);
+            let is_marker = match node {
+                Node::Html(html) => comment_content(&html.value) == Some(TOC_COMMENT),
+                Node::Paragraph(_) => text[range.clone()].trim() == TOC_LINE,
+                _ => false,
+            };
+            is_marker.then(|| (range, format!("{TOC_MARKER}\n")))
+        })
+        .collect()
+}
+
+/// fills the placeholders of the compiled slide at `index` with an agenda
+/// of the headings of the other slides.
+pub fn toc_render(html: &str, outline: &[OutlineHeading], index: usize) -> String {
+    if !html.contains(TOC_MARKER) {
+        return html.to_string();
+    }
+    let items = outline
+        .iter()
+        .filter(|x| x.slide != index && x.level <= TOC_DEPTH)
+        .map(|x| {
+            format!(
+                r##"<li class="toc-level-{}"><a href="#{}">{}</a></li>"##,
+                x.level,
+                escape_html(&x.id),
+                escape_html(&x.text)
+            )
+        })
+        .collect::<String>();
+    html.replace(
+        TOC_MARKER,
+        &format!(r#"<nav class="toc"><ul>{items}</ul></nav>"#),
+    )
+}
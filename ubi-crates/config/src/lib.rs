@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Display, mem::transmute};
+use std::{collections::HashMap, fmt::Display, mem::transmute, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,15 +8,45 @@ mod error;
 pub struct EmittedMarkdown<T: ToString> {
     pub current: usize,
     pub len: usize,
+    /// how many fragments of the slide are revealed
+    pub fragment: usize,
+    pub stack: Stack,
+    pub settings: SlideSettings,
     pub content: T,
 }
 
+/// per slide settings written in a `<!-- slide: ... -->` directive
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SlideSettings {
+    pub background_color: Option<String>,
+    /// an url the frontend can load as is
+    pub background_image: Option<String>,
+    /// the frontend styles centered, small, large and wide
+    pub classes: Vec<String>,
+    pub theme: Option<String>,
+    /// one of the built in layouts: title, section, two-column,
+    /// image-left, image-right, quote and full-image
+    pub layout: Option<String>,
+    /// overrides the `incremental_lists` of the front matter for this slide
+    pub incremental: Option<bool>,
+}
+
+/// where a slide stands among the sections and in the stack of its section
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct Stack {
+    pub section: usize,
+    pub sections: usize,
+    pub current: usize,
+    pub len: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmittedConfig {
     pub theme_notification: bool,
     pub live_config_reload: bool,
     pub keys: Keys,
     pub keys_help: String,
+    pub highlight_css: HighlightCss,
     pub port: u16,
 }
 
@@ -29,6 +59,7 @@ impl EmittedConfig {
             ..
         }: GlobalConfig,
         keys_help: String,
+        highlight_css: HighlightCss,
         port: u16,
     ) -> Self {
         Self {
@@ -36,6 +67,7 @@ impl EmittedConfig {
             live_config_reload,
             keys,
             keys_help,
+            highlight_css,
             port,
         }
     }
@@ -45,17 +77,156 @@ impl<T> EmittedMarkdown<T>
 where
     T: ToString,
 {
-    pub fn new(current: usize, len: usize, content: T) -> Self {
+    pub fn new(
+        current: usize,
+        len: usize,
+        fragment: usize,
+        stack: Stack,
+        settings: SlideSettings,
+        content: T,
+    ) -> Self {
         Self {
             current,
             len,
+            fragment,
+            stack,
+            settings,
             content,
         }
     }
 }
 
+/// per document settings written in the front matter of the markdown file
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct FrontMatter {
+    #[serde(deserialize_with = "scalar_text")]
+    pub title: Option<String>,
+    #[serde(deserialize_with = "scalar_text")]
+    pub author: Option<String>,
+    #[serde(deserialize_with = "scalar_text")]
+    pub date: Option<String>,
+    pub theme: Option<String>,
+    pub font_size: Option<FontSize>,
+    /// how the document is cut into slides, like `heading 2` or `marker slide`,
+    /// it takes precedence over the `slide_split` of the config file
+    pub slide_split: Option<String>,
+    /// takes precedence over the `sub_slide_split` of the config file
+    pub sub_slide_split: Option<String>,
+    /// used for both the light and the dark code palette
+    pub highlight_theme: Option<String>,
+    /// length of the talk in minutes, counted down in the presenter window
+    pub duration: Option<u64>,
+    /// reveals list items one at a time
+    pub incremental_lists: bool,
+}
+
+impl FrontMatter {
+    pub fn highlight_themes(&self, themes: &HighlightThemes) -> HighlightThemes {
+        match &self.highlight_theme {
+            Some(theme) => HighlightThemes {
+                light: theme.clone(),
+                dark: theme.clone(),
+            },
+            None => themes.clone(),
+        }
+    }
+}
+
+/// the text of a front matter value written as a number, a boolean or a date,
+/// like `title: 2024`, a toml date comes as a map of one entry holding its text
+fn scalar_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct ScalarText;
+
+    impl<'de> serde::de::Visitor<'de> for ScalarText {
+        type Value = Option<String>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a text, a number, a boolean or a date")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(Some(v.to_string()))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+            Ok(Some(v.to_string()))
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(Some(v.to_string()))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+            Ok(Some(v.to_string()))
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+            Ok(Some(v.to_string()))
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            deserializer.deserialize_any(ScalarText)
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            Ok(map.next_entry::<String, String>()?.map(|(_, text)| text))
+        }
+    }
+
+    deserializer.deserialize_any(ScalarText)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct EmittedFrontMatter {
+    pub front_matter: FrontMatter,
+    pub highlight_css: HighlightCss,
+}
+
+/// what the presenter window shows next to the current slide
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct EmittedPresenter {
+    pub next: String,
+    pub notes: String,
+}
+
+/// a heading of the document and the slide it is on
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct OutlineHeading {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+    pub slide: usize,
+}
+
+/// the headings of every slide of the document, in order
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct EmittedOutline {
+    pub headings: Vec<OutlineHeading>,
+}
+
 pub const CONTENT_EVENT: &str = "content";
 pub const CONFIG_EVENT: &str = "config";
+pub const FRONT_MATTER_EVENT: &str = "front_matter";
+pub const PRESENTER_EVENT: &str = "presenter";
+pub const OUTLINE_EVENT: &str = "outline";
 
 #[derive(Clone, Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -67,6 +238,44 @@ pub enum FontSize {
     VeryBig,
 }
 
+/// where a document gets cut into slides
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SlideSplit {
+    /// top level `---` breaks
+    #[default]
+    ThematicBreak,
+    /// every heading of this level or above starts a slide
+    Heading(u8),
+    /// top level `<!-- marker -->` comments holding this text
+    Marker(String),
+    /// top level lines holding only this text, like `--`
+    Line(String),
+    None,
+}
+
+impl FromStr for SlideSplit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (mode, value) = s.split_once(' ').unwrap_or((s, ""));
+        let value = value.trim();
+        match mode {
+            "thematic_break" | "---" => Ok(Self::ThematicBreak),
+            "--" => Ok(Self::Line(mode.to_string())),
+            "none" => Ok(Self::None),
+            "heading" => value
+                .parse()
+                .map(Self::Heading)
+                .map_err(|_| format!("invalid heading level `{value}`")),
+            "marker" if !value.is_empty() => Ok(Self::Marker(value.to_string())),
+            "line" if !value.is_empty() => Ok(Self::Line(value.to_string())),
+            _ => Err(format!("unknown slide split `{s}`")),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Action {
     Print,
@@ -77,24 +286,103 @@ pub enum Action {
     IncreaseFontsize,
     DecreaseFontsize,
     Help,
+    Presenter,
+    SlideUp,
+    SlideDown,
+    Overview,
+    FirstSlide,
+    LastSlide,
+    GotoSlide,
+    HistoryBack,
+    HistoryForward,
+    Toc,
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct InitConfig {
     pub conf: GlobalConfig,
     pub keys_help: String,
+    pub highlight_css: HighlightCss,
     pub port: u16,
 }
 
+/// syntect theme names used for code blocks on light and dark themes
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct HighlightThemes {
+    pub light: String,
+    pub dark: String,
+}
+
+impl Default for HighlightThemes {
+    fn default() -> Self {
+        Self {
+            light: "InspiredGitHub".to_string(),
+            dark: "base16-ocean.dark".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, Default, PartialEq)]
+pub struct HighlightCss {
+    pub light: String,
+    pub dark: String,
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct GlobalConfig {
     pub default_theme: String,
     pub default_font_size: FontSize,
     pub theme_notification: bool,
     pub live_config_reload: bool,
+    /// the split of every document whose front matter sets none
+    #[serde(default)]
+    pub slide_split: SlideSplit,
+    /// cuts every slide into a vertical stack of sub slides
+    #[serde(default = "default_sub_slide_split")]
+    pub sub_slide_split: SlideSplit,
+    #[serde(default)]
+    pub highlight_themes: HighlightThemes,
+    /// added to the default aliases, an alias given here wins over a default one
+    #[serde(
+        default = "default_code_aliases",
+        deserialize_with = "merge_code_aliases"
+    )]
+    pub code_aliases: HashMap<String, String>,
+    #[serde(default)]
     pub keys: Keys,
 }
 
+fn default_sub_slide_split() -> SlideSplit {
+    SlideSplit::None
+}
+
+/// fence languages that syntect does not know by name
+fn default_code_aliases() -> HashMap<String, String> {
+    [
+        ("shell", "bash"),
+        ("console", "bash"),
+        ("zsh", "bash"),
+        ("jsx", "javascript"),
+        ("ts", "javascript"),
+        ("tsx", "javascript"),
+        ("typescript", "javascript"),
+        ("golang", "go"),
+        ("c++", "cpp"),
+    ]
+    .into_iter()
+    .map(|(alias, lang)| (alias.to_string(), lang.to_string()))
+    .collect()
+}
+
+fn merge_code_aliases<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut aliases = default_code_aliases();
+    aliases.extend(HashMap::<String, String>::deserialize(deserializer)?);
+    Ok(aliases)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum KeyName {
@@ -232,7 +520,9 @@ impl From<u16> for KeyName {
     }
 }
 
+/// a key left out of the config keeps its default
 #[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(default)]
 pub struct Keys {
     pub print: KeyName,
     pub next_theme: KeyName,
@@ -242,6 +532,18 @@ pub struct Keys {
     pub increase_fontsize: KeyName,
     pub decrease_fontsize: KeyName,
     pub help: KeyName,
+    pub presenter: KeyName,
+    pub slide_up: KeyName,
+    pub slide_down: KeyName,
+    pub overview: KeyName,
+    pub first_slide: KeyName,
+    pub last_slide: KeyName,
+    /// jumps to the slide number typed before it
+    pub goto_slide: KeyName,
+    /// the document opened before following a link
+    pub history_back: KeyName,
+    pub history_forward: KeyName,
+    pub toc: KeyName,
 }
 
 impl Keys {
@@ -255,6 +557,16 @@ impl Keys {
             increase_fontsize,
             decrease_fontsize,
             help,
+            presenter,
+            slide_up,
+            slide_down,
+            overview,
+            first_slide,
+            last_slide,
+            goto_slide,
+            history_back,
+            history_forward,
+            toc,
         } = self;
         HashMap::from([
             (print, Action::Print),
@@ -265,6 +577,16 @@ impl Keys {
             (increase_fontsize, Action::IncreaseFontsize),
             (decrease_fontsize, Action::DecreaseFontsize),
             (help, Action::Help),
+            (presenter, Action::Presenter),
+            (slide_up, Action::SlideUp),
+            (slide_down, Action::SlideDown),
+            (overview, Action::Overview),
+            (first_slide, Action::FirstSlide),
+            (last_slide, Action::LastSlide),
+            (goto_slide, Action::GotoSlide),
+            (history_back, Action::HistoryBack),
+            (history_forward, Action::HistoryForward),
+            (toc, Action::Toc),
         ])
     }
 }
@@ -280,6 +602,16 @@ impl Display for Keys {
             increase_fontsize,
             decrease_fontsize,
             help,
+            presenter,
+            slide_up,
+            slide_down,
+            overview,
+            first_slide,
+            last_slide,
+            goto_slide,
+            history_back,
+            history_forward,
+            toc,
         } = self;
         write!(
             f,
@@ -294,6 +626,17 @@ impl Display for Keys {
 | **{increase_fontsize:?}**  |   __increase fontsize__ |
 | **{decrease_fontsize:?}**  |   __decrease fontsize__ |
 |       **{help:?}**         |         __help__        |
+|     **{presenter:?}**      |   __presenter window__  |
+|     **{slide_up:?}**       |     __sub slide up__    |
+|    **{slide_down:?}**      |    __sub slide down__   |
+|     **{overview:?}**       |   __slides overview__   |
+|    **{first_slide:?}**     |     __first slide__     |
+|     **{last_slide:?}**     |      __last slide__     |
+|  **N {goto_slide:?}**      |  __go to slide N__      |
+|       **N Enter**          |  __go to slide N__      |
+|   **{history_back:?}**     |  __previous document__  |
+|  **{history_forward:?}**   |    __next document__    |
+|         **{toc:?}**        |  __table of contents__  |
 |       **Esc**            |   __hide this message__ |
 "#
         )
@@ -311,6 +654,16 @@ impl Default for Keys {
             increase_fontsize: KeyName::Equal,
             decrease_fontsize: KeyName::Minus,
             help: KeyName::Slash,
+            presenter: KeyName::S,
+            slide_up: KeyName::Up,
+            slide_down: KeyName::Down,
+            overview: KeyName::O,
+            first_slide: KeyName::B,
+            last_slide: KeyName::E,
+            goto_slide: KeyName::G,
+            history_back: KeyName::Q,
+            history_forward: KeyName::W,
+            toc: KeyName::T,
         }
     }
 }
@@ -364,6 +717,10 @@ impl Default for GlobalConfig {
             default_font_size: FontSize::Small,
             theme_notification: true,
             live_config_reload: true,
+            slide_split: SlideSplit::default(),
+            sub_slide_split: default_sub_slide_split(),
+            highlight_themes: HighlightThemes::default(),
+            code_aliases: default_code_aliases(),
             keys: Keys::default(),
         }
     }